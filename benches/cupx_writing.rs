@@ -63,11 +63,31 @@ fn bench_write_with_multiple_pictures(c: &mut Criterion) {
     });
 }
 
+fn bench_write_unchecked_with_multiple_pictures(c: &mut Criterion) {
+    let picture_data_small = vec![0u8; 10000];
+    let picture_data_medium = vec![0u8; 34858];
+    let picture_data_large = vec![0u8; 100000];
+
+    c.bench_function("CupxWriter::write_unchecked (3 pictures)", |b| {
+        let mut buffer = Vec::with_capacity(200_000);
+        b.iter(|| {
+            buffer.clear();
+            let cup_file = CupFile::default();
+            let mut writer = CupxWriter::new(&cup_file);
+            writer.add_picture("small.jpg", picture_data_small.as_slice());
+            writer.add_picture("medium.jpg", picture_data_medium.as_slice());
+            writer.add_picture("large.jpg", picture_data_large.as_slice());
+            writer.write_unchecked(Cursor::new(&mut buffer)).unwrap();
+        })
+    });
+}
+
 criterion_group!(
     benches,
     bench_write_empty,
     bench_write_with_single_picture,
     bench_write_with_picture_from_path,
-    bench_write_with_multiple_pictures
+    bench_write_with_multiple_pictures,
+    bench_write_unchecked_with_multiple_pictures
 );
 criterion_main!(benches);
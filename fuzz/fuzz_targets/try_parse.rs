@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seeyou_cupx::CupxFile;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = CupxFile::try_parse(data);
+});
@@ -0,0 +1,78 @@
+use crate::error::Error;
+use crate::reader::{CupxFile, picture_name};
+use crate::writer::{default_file_options, sized_file_options, validate_filename};
+use std::collections::HashSet;
+use std::io::{Read, Seek, Write};
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Writes a copy of this file with additional pictures.
+    ///
+    /// ZIP archives can't be extended in place, and the pics archive is followed
+    /// by the points archive, so the pics archive is rebuilt: all existing
+    /// entries are copied without recompressing them, the new pictures are added,
+    /// and the points archive is appended unchanged. A new picture replaces an
+    /// existing picture with the same name (case-insensitive).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let photo = std::fs::read("photo.jpg")?;
+    /// cupx.append_pictures(&[("photo.jpg", &photo)], File::create("updated.cupx")?)?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidFilename`] if any of the new filenames is invalid,
+    /// or an error if reading from the source or writing to the output fails.
+    pub fn append_pictures<W: Write + Seek>(
+        &mut self,
+        new: &[(&str, &[u8])],
+        out: W,
+    ) -> Result<(), Error> {
+        for (filename, _) in new {
+            validate_filename(filename)?;
+        }
+
+        let replaced = new
+            .iter()
+            .map(|(filename, _)| filename.to_lowercase())
+            .collect::<HashSet<_>>();
+
+        let root_pictures = self.root_pictures;
+        let mut pics_zip = zip::ZipWriter::new(out);
+
+        if let Some(pics_archive) = self.pics_archive.as_mut() {
+            for index in 0..pics_archive.len() {
+                let entry = pics_archive.by_index_raw(index)?;
+                if !entry.is_dir()
+                    && picture_name(entry.name(), root_pictures)
+                        .is_some_and(|name| replaced.contains(&name.to_lowercase()))
+                {
+                    continue;
+                }
+
+                pics_zip.raw_copy_file(entry)?;
+            }
+        }
+
+        let options = default_file_options();
+        // New pictures follow the layout of the existing ones
+        let prefix = if root_pictures { "" } else { "pics/" };
+        for (filename, data) in new {
+            let options = sized_file_options(options, data.len() as u64);
+            pics_zip.start_file(format!("{prefix}{filename}"), options)?;
+            pics_zip.write_all(data)?;
+        }
+
+        let mut out = pics_zip.finish()?;
+
+        self.copy_points_archive(&mut out)?;
+
+        Ok(())
+    }
+}
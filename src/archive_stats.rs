@@ -0,0 +1,16 @@
+/// Structural information about the two ZIP archives of a CUPX file.
+///
+/// Returned by [`CupxFile::archive_stats`](crate::CupxFile::archive_stats). For
+/// files using the single-ZIP layout, both halves describe the same archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ArchiveStats {
+    /// Number of entries in the pics archive, including directories.
+    pub pics_entry_count: usize,
+    /// Number of entries in the points archive.
+    pub points_entry_count: usize,
+    /// Size of the pics archive in bytes.
+    pub pics_archive_size: u64,
+    /// Size of the points archive in bytes.
+    pub points_archive_size: u64,
+}
@@ -0,0 +1,18 @@
+/// The geographic extent of a set of waypoints, in decimal degrees.
+///
+/// Returned by [`CupxFile::bounding_box`](crate::CupxFile::bounding_box). The
+/// box is the plain minimum and maximum of the coordinates, so for waypoints on
+/// both sides of the antimeridian it spans the whole globe in longitude instead
+/// of wrapping around.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BoundingBox {
+    /// Southernmost latitude.
+    pub min_lat: f64,
+    /// Northernmost latitude.
+    pub max_lat: f64,
+    /// Westernmost longitude.
+    pub min_lon: f64,
+    /// Easternmost longitude.
+    pub max_lon: f64,
+}
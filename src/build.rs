@@ -0,0 +1,58 @@
+use crate::error::Error;
+use crate::writer::CupxWriter;
+use seeyou_cup::CupFile;
+use std::path::Path;
+
+/// Writes a CUPX file from CUP data and a directory of pictures in one call.
+///
+/// All files directly inside `pics_dir`, including symlinks to files, are
+/// added as pictures under their filename, in alphabetical order.
+/// Subdirectories and hidden files (starting with `.`, like `.DS_Store`) are
+/// skipped. Without a `pics_dir`, the file only contains the CUP data.
+///
+/// For more control, e.g. over the picture names or the layout, use
+/// [`CupxWriter`] directly.
+///
+/// # Examples
+///
+/// ```no_run
+/// use seeyou_cupx::build;
+/// use seeyou_cup::CupFile;
+/// use std::path::Path;
+///
+/// let (cup_file, _) = CupFile::from_path("waypoints.cup")?;
+/// build(&cup_file, Some(Path::new("photos")), Path::new("waypoints.cupx"))?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if `pics_dir` can't be read, if a filename is not valid
+/// UTF-8 ([`Error::InvalidFilename`]), or if writing the CUPX file fails.
+pub fn build(cup: &CupFile, pics_dir: Option<&Path>, out: &Path) -> Result<(), Error> {
+    let mut pictures = Vec::new();
+    if let Some(pics_dir) = pics_dir {
+        for entry in std::fs::read_dir(pics_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if !std::fs::metadata(&path)?.is_file() {
+                continue;
+            }
+
+            let filename = entry
+                .file_name()
+                .into_string()
+                .map_err(|name| Error::InvalidFilename(name.to_string_lossy().into_owned()))?;
+            if !filename.starts_with('.') {
+                pictures.push((filename, path));
+            }
+        }
+    }
+    pictures.sort();
+
+    let mut writer = CupxWriter::new(cup);
+    for (filename, path) in &pictures {
+        writer.add_picture(filename, path.as_path());
+    }
+    writer.write_to_path(out)
+}
@@ -0,0 +1,138 @@
+use crate::error::Error;
+use crate::reader::CupxFile;
+use crate::text_entry::MAX_TEXT_ENTRY_SIZE;
+use crate::writer::CHECKSUMS_FILENAME;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::{Read, Seek};
+
+/// The result of checking a picture against the checksum manifest.
+///
+/// Returned by [`CupxFile::verify_checksums`]. All names are relative to the
+/// `pics/` directory of the archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumResult {
+    /// The picture matches the SHA-256 digest in the manifest.
+    Match(String),
+    /// The picture exists, but its content doesn't match the manifest.
+    Mismatch(String),
+    /// The picture is listed in the manifest, but not in the archive.
+    Missing(String),
+    /// The picture exists in the archive, but is not listed in the manifest.
+    Unlisted(String),
+}
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Checks the pictures against the `CHECKSUMS.txt` manifest in the points
+    /// archive.
+    ///
+    /// The manifest is written by
+    /// [`CupxWriter::write_checksums`](crate::CupxWriter::write_checksums) and
+    /// uses the format of `sha256sum`, with one `<hex digest>  <name>` line per
+    /// picture. Lines that don't follow this format are ignored. The results
+    /// for the listed pictures come first, in manifest order, followed by any
+    /// pictures that are not listed, in archive order.
+    ///
+    /// Returns an empty list if the points archive contains no manifest.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::{ChecksumResult, CupxFile};
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for result in cupx.verify_checksums()? {
+    ///     if !matches!(result, ChecksumResult::Match(_)) {
+    ///         println!("{result:?}");
+    ///     }
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest or a picture cannot be read, or if the
+    /// file was created with [`from_archives`](Self::from_archives).
+    pub fn verify_checksums(&mut self) -> Result<Vec<ChecksumResult>, Error> {
+        let Some(manifest) =
+            self.read_points_entry(CHECKSUMS_FILENAME, Some(MAX_TEXT_ENTRY_SIZE))?
+        else {
+            return Ok(Vec::new());
+        };
+        let manifest = String::from_utf8_lossy(&manifest);
+
+        let mut results = Vec::new();
+        let mut listed = HashSet::new();
+        for (name, expected) in manifest.lines().filter_map(parse_manifest_line) {
+            listed.insert(name);
+            let result = match self.picture(name) {
+                None => ChecksumResult::Missing(name.to_string()),
+                Some(_) if self.picture_digest(name)? == expected => {
+                    ChecksumResult::Match(name.to_string())
+                }
+                Some(_) => ChecksumResult::Mismatch(name.to_string()),
+            };
+            results.push(result);
+        }
+
+        results.extend(
+            self.picture_names()
+                .filter(|name| !listed.contains(name.as_str()))
+                .map(ChecksumResult::Unlisted),
+        );
+
+        Ok(results)
+    }
+}
+
+/// Formats the checksum manifest in the format of `sha256sum`.
+pub(crate) fn format_manifest(checksums: &[(String, [u8; 32])]) -> String {
+    let mut manifest = String::new();
+    for (name, digest) in checksums {
+        for byte in digest {
+            manifest.push_str(&format!("{byte:02x}"));
+        }
+        manifest.push_str("  ");
+        manifest.push_str(name);
+        manifest.push('\n');
+    }
+    manifest
+}
+
+/// Parses a `<hex digest>  <name>` line of the checksum manifest.
+///
+/// The binary mode marker of `sha256sum` (`<hex digest> *<name>`) is accepted
+/// as well.
+fn parse_manifest_line(line: &str) -> Option<(&str, [u8; 32])> {
+    let (hex, name) = line.split_once(' ')?;
+    let name = name.strip_prefix([' ', '*'])?;
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) || name.is_empty() {
+        return None;
+    }
+
+    let mut digest = [0; 32];
+    for (byte, chunk) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+    }
+    Some((name, digest))
+}
+
+/// A reader that feeds all data read through it into a SHA-256 hasher.
+pub(crate) struct HashingReader<'h, R> {
+    inner: R,
+    hasher: &'h mut Sha256,
+}
+
+impl<'h, R> HashingReader<'h, R> {
+    pub(crate) fn new(inner: R, hasher: &'h mut Sha256) -> Self {
+        Self { inner, hasher }
+    }
+}
+
+impl<R: Read> Read for HashingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::error::Error;
+use crate::reader::CupxFile;
+use seeyou_cup::Encoding;
+use std::io::{BufRead, Cursor, Read, Seek};
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Returns an iterator over the lines of the raw `POINTS.CUP` text.
+    ///
+    /// This allows searching the CUP data for content that the parsed
+    /// [`waypoints`](Self::waypoints) don't expose, like a substring of an
+    /// unknown column. The lines are decoded one at a time, using the
+    /// [configured encoding](crate::ParseOptions::encoding) or the same
+    /// automatic detection as for parsing, and returned without their line
+    /// endings. The header row is the first line.
+    ///
+    /// Since the points archive is not kept open after parsing, the compressed
+    /// `POINTS.CUP` is decompressed into memory first, but the decoded text is
+    /// never materialized as a whole. The returned iterator doesn't borrow
+    /// `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for line in cupx.cup_lines()? {
+    ///     let line = line?;
+    ///     if line.contains("122.500") {
+    ///         println!("{line}");
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading the archive fails, or if the file was
+    /// created with [`from_archives`](Self::from_archives).
+    pub fn cup_lines(
+        &mut self,
+    ) -> Result<impl Iterator<Item = std::io::Result<String>> + use<R>, Error> {
+        let data = self
+            .read_points_entry("POINTS.CUP", None)?
+            .ok_or(zip::result::ZipError::FileNotFound)?;
+
+        // Same detection as in `seeyou_cup`, which decides for the whole file
+        let encoding = self
+            .encoding
+            .unwrap_or_else(|| match std::str::from_utf8(&data) {
+                Ok(_) => Encoding::Utf8,
+                Err(_) => Encoding::Windows1252,
+            });
+        let decoder = match encoding {
+            Encoding::Utf8 => encoding_rs::UTF_8,
+            Encoding::Windows1252 => encoding_rs::WINDOWS_1252,
+        };
+
+        let mut data = Cursor::new(data);
+        if data.get_ref().starts_with(b"\xEF\xBB\xBF") {
+            data.set_position(3);
+        }
+
+        Ok(data.split(b'\n').map(move |line| {
+            let line = line?;
+            let line = line.strip_suffix(b"\r").unwrap_or(&line);
+            Ok(decoder.decode_without_bom_handling(line).0.into_owned())
+        }))
+    }
+}
@@ -0,0 +1,168 @@
+use crate::error::Error;
+use crate::reader::{CupxFile, same_content};
+use seeyou_cup::Waypoint;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufReader, Read, Seek};
+
+/// The differences between the logical contents of two CUPX files.
+///
+/// Returned by [`CupxFile::diff`]. Waypoints and pictures are identified by
+/// name, while tasks are compared by their position in the file since they
+/// don't necessarily have a unique name. Waypoints sharing a name are paired
+/// in the order they appear in each file, so such a name may be listed more
+/// than once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CupxDiff {
+    /// Names of waypoints that only exist in the other file.
+    pub added_waypoints: Vec<String>,
+    /// Names of waypoints that only exist in this file.
+    pub removed_waypoints: Vec<String>,
+    /// Names of waypoints that exist in both files but differ.
+    pub changed_waypoints: Vec<String>,
+    /// Indices of tasks that only exist in the other file.
+    pub added_tasks: Vec<usize>,
+    /// Indices of tasks that only exist in this file.
+    pub removed_tasks: Vec<usize>,
+    /// Indices of tasks that exist in both files but differ.
+    pub changed_tasks: Vec<usize>,
+    /// Names of pictures that only exist in the other file.
+    pub added_pictures: Vec<String>,
+    /// Names of pictures that only exist in this file.
+    pub removed_pictures: Vec<String>,
+    /// Names of pictures that exist in both files but have different content.
+    pub changed_pictures: Vec<String>,
+}
+
+impl CupxDiff {
+    /// Returns `true` if the two files have the same logical contents.
+    pub fn is_empty(&self) -> bool {
+        self.added_waypoints.is_empty()
+            && self.removed_waypoints.is_empty()
+            && self.changed_waypoints.is_empty()
+            && self.added_tasks.is_empty()
+            && self.removed_tasks.is_empty()
+            && self.changed_tasks.is_empty()
+            && self.added_pictures.is_empty()
+            && self.removed_pictures.is_empty()
+            && self.changed_pictures.is_empty()
+    }
+}
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Compares the logical contents of this file with `other`.
+    ///
+    /// "Added" entries only exist in `other`, "removed" entries only exist in
+    /// `self`. Pictures that exist in both files are compared by their size and
+    /// CRC-32 first, and then by streaming their decompressed bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut old, _) = CupxFile::from_path("old.cupx")?;
+    /// let (mut new, _) = CupxFile::from_path("new.cupx")?;
+    ///
+    /// let diff = old.diff(&mut new)?;
+    /// for name in &diff.added_waypoints {
+    ///     println!("Added: {}", name);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading a picture from either file fails.
+    pub fn diff<R2: Read + Seek>(&mut self, other: &mut CupxFile<R2>) -> Result<CupxDiff, Error> {
+        let mut diff = CupxDiff::default();
+
+        let waypoints = waypoints_by_name(self.waypoints());
+        let other_waypoints = waypoints_by_name(other.waypoints());
+
+        let mut seen = HashMap::new();
+        for waypoint in self.waypoints() {
+            let name = waypoint.name.as_str();
+            let index = occurrence(&mut seen, name);
+            match other_waypoints
+                .get(name)
+                .and_then(|others| others.get(index))
+            {
+                None => diff.removed_waypoints.push(waypoint.name.clone()),
+                Some(other) if *other != waypoint => {
+                    diff.changed_waypoints.push(waypoint.name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        let mut seen = HashMap::new();
+        for waypoint in other.waypoints() {
+            let name = waypoint.name.as_str();
+            let index = occurrence(&mut seen, name);
+            if waypoints
+                .get(name)
+                .is_none_or(|waypoints| index >= waypoints.len())
+            {
+                diff.added_waypoints.push(waypoint.name.clone());
+            }
+        }
+
+        let tasks = self.tasks();
+        let other_tasks = other.tasks();
+        for index in 0..tasks.len().max(other_tasks.len()) {
+            match (tasks.get(index), other_tasks.get(index)) {
+                (Some(task), Some(other)) if task != other => diff.changed_tasks.push(index),
+                (Some(_), None) => diff.removed_tasks.push(index),
+                (None, Some(_)) => diff.added_tasks.push(index),
+                _ => {}
+            }
+        }
+
+        let pictures = self.picture_names().collect::<Vec<_>>();
+        let other_pictures = other.picture_names().collect::<Vec<_>>();
+        let picture_set = pictures.iter().collect::<HashSet<_>>();
+        let other_picture_set = other_pictures.iter().collect::<HashSet<_>>();
+
+        for name in &pictures {
+            if other_picture_set.contains(name) {
+                let info = self.picture(name).map(|info| (info.size, info.crc32));
+                let other_info = other.picture(name).map(|info| (info.size, info.crc32));
+                let same = info == other_info
+                    && same_content(
+                        BufReader::new(self.read_picture(name)?),
+                        BufReader::new(other.read_picture(name)?),
+                    )?;
+                if !same {
+                    diff.changed_pictures.push(name.clone());
+                }
+            } else {
+                diff.removed_pictures.push(name.clone());
+            }
+        }
+        for name in &other_pictures {
+            if !picture_set.contains(name) {
+                diff.added_pictures.push(name.clone());
+            }
+        }
+
+        Ok(diff)
+    }
+}
+
+/// Groups the waypoints by name, keeping the order of waypoints sharing a name.
+fn waypoints_by_name(waypoints: &[Waypoint]) -> HashMap<&str, Vec<&Waypoint>> {
+    let mut by_name = HashMap::<_, Vec<_>>::new();
+    for waypoint in waypoints {
+        by_name
+            .entry(waypoint.name.as_str())
+            .or_default()
+            .push(waypoint);
+    }
+    by_name
+}
+
+/// Returns how often `name` was seen before, and counts this occurrence.
+fn occurrence<'a>(seen: &mut HashMap<&'a str, usize>, name: &'a str) -> usize {
+    let count = seen.entry(name).or_default();
+    *count += 1;
+    *count - 1
+}
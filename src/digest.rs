@@ -0,0 +1,146 @@
+use crate::error::Error;
+use crate::reader::CupxFile;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+/// Size of the buffer used to stream pictures through the hasher.
+const BUFFER_SIZE: usize = 8192;
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Computes the SHA-256 digest of the decompressed picture data.
+    ///
+    /// The picture is streamed through the hasher using a fixed-size buffer, so
+    /// it is never loaded into memory completely. The filename is resolved like
+    /// in [`read_picture`](Self::read_picture).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let digest = cupx.picture_digest("photo.jpg")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the picture doesn't exist or cannot be read.
+    pub fn picture_digest(&mut self, filename: &str) -> Result<[u8; 32], Error> {
+        let mut reader = self.read_picture(filename)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; BUFFER_SIZE];
+        loop {
+            let n = reader.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+        }
+
+        Ok(hasher.finalize().into())
+    }
+
+    /// Computes the SHA-256 digests of all pictures.
+    ///
+    /// The keys are the picture filenames as returned by
+    /// [`picture_names`](Self::picture_names).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for (name, digest) in cupx.all_picture_digests()? {
+    ///     let hex = digest.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    ///     println!("{hex}  {name}");
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any picture cannot be read.
+    pub fn all_picture_digests(&mut self) -> Result<HashMap<String, [u8; 32]>, Error> {
+        let names = self.picture_names().collect::<Vec<_>>();
+
+        let mut digests = HashMap::with_capacity(names.len());
+        for name in names {
+            let digest = self.picture_digest(&name)?;
+            digests.insert(name, digest);
+        }
+
+        Ok(digests)
+    }
+
+    /// Groups pictures with byte-identical content.
+    ///
+    /// Each inner `Vec` contains the names of pictures sharing the same content,
+    /// as returned by [`picture_names`](Self::picture_names). Pictures without
+    /// duplicates are not included. Both the groups and the names in them are
+    /// in archive order.
+    ///
+    /// Only pictures with the same size and CRC-32 checksum in the ZIP headers
+    /// are compared by their SHA-256 digests, so pictures with unique headers
+    /// are never decompressed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for names in cupx.duplicate_pictures()? {
+    ///     println!("identical: {}", names.join(", "));
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the compared pictures cannot be read.
+    pub fn duplicate_pictures(&mut self) -> Result<Vec<Vec<String>>, Error> {
+        let mut keys = Vec::new();
+        let mut candidates = HashMap::<_, Vec<String>>::new();
+        for name in self.picture_names().collect::<Vec<_>>() {
+            let Some(info) = self.picture(&name) else {
+                continue;
+            };
+            let names = candidates
+                .entry((info.size, info.crc32))
+                .or_insert_with(|| {
+                    keys.push((info.size, info.crc32));
+                    Vec::new()
+                });
+            names.push(name);
+        }
+
+        let mut groups = Vec::new();
+        for key in keys {
+            let names = candidates.remove(&key).unwrap_or_default();
+            if names.len() < 2 {
+                continue;
+            }
+
+            let mut by_digest: Vec<([u8; 32], Vec<String>)> = Vec::new();
+            for name in names {
+                let digest = self.picture_digest(&name)?;
+                match by_digest.iter_mut().find(|(other, _)| *other == digest) {
+                    Some((_, group)) => group.push(name),
+                    None => by_digest.push((digest, vec![name])),
+                }
+            }
+
+            groups.extend(
+                by_digest
+                    .into_iter()
+                    .map(|(_, group)| group)
+                    .filter(|group| group.len() > 1),
+            );
+        }
+
+        Ok(groups)
+    }
+}
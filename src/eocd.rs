@@ -0,0 +1,74 @@
+/// The fixed-size part of a ZIP End of Central Directory (EOCD) record.
+///
+/// CUPX files are located by searching for these records, since every ZIP
+/// archive ends with one. All fields are stored in little-endian byte order.
+/// Values that don't fit into their field in ZIP64 archives are set to their
+/// maximum, with the actual values in the ZIP64 EOCD record.
+///
+/// # Examples
+///
+/// ```
+/// use seeyou_cupx::parse_eocd;
+///
+/// let mut record = vec![0; 22];
+/// record[..4].copy_from_slice(b"PK\x05\x06");
+/// record[10] = 3;
+/// let eocd = parse_eocd(&record).unwrap();
+/// assert_eq!(eocd.entry_count, 3);
+/// assert_eq!(eocd.record_size(), 22);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Eocd {
+    /// Total number of entries in the central directory.
+    pub entry_count: u16,
+    /// Size of the central directory in bytes.
+    pub central_directory_size: u32,
+    /// Offset of the central directory, relative to the start of the archive.
+    pub central_directory_offset: u32,
+    /// Length of the archive comment following the record.
+    pub comment_length: u16,
+}
+
+impl Eocd {
+    /// Signature at the start of every EOCD record.
+    pub const SIGNATURE: &[u8; 4] = b"PK\x05\x06";
+    /// Size of the record without the comment.
+    pub const MIN_SIZE: usize = 22;
+
+    /// Returns the size of the record including the comment.
+    ///
+    /// For the last record of an archive, this is the distance from the
+    /// signature to the end of the archive.
+    pub fn record_size(&self) -> u64 {
+        Self::MIN_SIZE as u64 + self.comment_length as u64
+    }
+}
+
+/// Parses the EOCD record at the start of `bytes`.
+///
+/// Returns `None` if `bytes` is shorter than [`Eocd::MIN_SIZE`] or doesn't
+/// start with [`Eocd::SIGNATURE`]. The comment itself is not required to be
+/// part of `bytes`.
+pub fn parse_eocd(bytes: &[u8]) -> Option<Eocd> {
+    let record = bytes.get(..Eocd::MIN_SIZE)?;
+    if !record.starts_with(Eocd::SIGNATURE) {
+        return None;
+    }
+
+    let u16_at = |offset: usize| u16::from_le_bytes([record[offset], record[offset + 1]]);
+    let u32_at = |offset: usize| {
+        u32::from_le_bytes([
+            record[offset],
+            record[offset + 1],
+            record[offset + 2],
+            record[offset + 3],
+        ])
+    };
+
+    Some(Eocd {
+        entry_count: u16_at(10),
+        central_directory_size: u32_at(12),
+        central_directory_offset: u32_at(16),
+        comment_length: u16_at(20),
+    })
+}
@@ -1,20 +1,214 @@
+use crate::picture_format::PictureFormat;
+
 /// Non-fatal warnings that may occur when parsing a CUPX file.
 ///
 /// Warnings indicate issues that don't prevent the file from being read,
 /// but may indicate missing data or parsing concerns.
-#[derive(Debug, Clone)]
+///
+/// `Warning` implements [`PartialEq`], but not [`Eq`], since
+/// [`InvalidCoordinates`](Self::InvalidCoordinates) contains `f64` values.
+///
+/// New variants may be added in minor releases, so `match` statements on
+/// warnings need a wildcard `_ =>` arm.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Warning {
     /// The CUPX file does not contain a pictures archive.
     NoPicturesArchive,
+    /// The comment length in the EOCD record of the pictures archive points
+    /// past the start of the points archive.
+    ///
+    /// The comment length was ignored and the boundary between the two archives
+    /// was assumed to be directly after the fixed-size part of the EOCD record.
+    /// If the pictures archive can't be opened with the corrupt comment length,
+    /// the file is loaded without pictures.
+    SuspiciousEocdComment,
+    /// The pictures archive contains a `POINTS.CUP` file.
+    ///
+    /// This file is ignored, and the `POINTS.CUP` from the points archive is used
+    /// instead. It may indicate that the archives are in the wrong order.
+    PointsCupInPicsArchive,
     /// An issue occurred while parsing the CUP file data.
     ///
     /// The `message` describes the issue, and `line` indicates the line number
     /// in the CUP file where it occurred, if available.
     CupParseIssue { message: String, line: Option<u64> },
+    /// A waypoint has a latitude outside of `[-90, 90]` or a longitude outside
+    /// of `[-180, 180]`.
+    ///
+    /// The CUP parser already skips most waypoints with out-of-range coordinates
+    /// (reporting them as [`CupParseIssue`](Self::CupParseIssue)), so this acts as a
+    /// safety net for any values that slip through, like `NaN`.
+    InvalidCoordinates {
+        name: String,
+        latitude: f64,
+        longitude: f64,
+    },
+    /// The content of a picture doesn't match the format indicated by its
+    /// file extension, e.g. a PNG image stored as `photo.jpg`.
+    ///
+    /// Some devices refuse to display such pictures. This is only checked with
+    /// [`ParseOptions::eager_validate`](crate::ParseOptions::eager_validate), and
+    /// only for pictures with a known extension and a detectable format.
+    PictureFormatMismatch {
+        name: String,
+        extension: String,
+        detected: PictureFormat,
+    },
+    /// The name of a picture is not marked as UTF-8 and contains non-ASCII
+    /// bytes.
+    ///
+    /// Such names usually come from a legacy code page, e.g. on Windows. They are
+    /// decoded as CP437 like in other ZIP tools, so the picture may not be found
+    /// under the name that waypoints use to reference it. `raw` contains the
+    /// undecoded name without the `pics/` prefix.
+    NonUtf8PictureName { raw: Vec<u8> },
+    /// The pics archive contains no `pics/` folder, but image files at its top
+    /// level.
+    ///
+    /// These files are used as the pictures instead, and their names are
+    /// returned as-is by [`CupxFile::picture_names`](crate::CupxFile::picture_names).
+    /// Other applications may not find them.
+    PicturesWithoutPrefix,
+    /// The central directory of the points archive is damaged, and the CUP data
+    /// was recovered from the local header of `POINTS.CUP`.
+    ///
+    /// Only reported with [`ParseOptions::recover`](crate::ParseOptions::recover).
+    /// Supplementary CUP files of the points archive are not available.
+    RecoveredFromCorruptArchive,
+    /// There are `bytes` of unexpected data between the pics archive and the
+    /// points archive.
+    ///
+    /// The start of the points archive is derived from the central directory
+    /// offset and size in its EOCD record. This is only checked with
+    /// [`ParseOptions::eager_validate`](crate::ParseOptions::eager_validate).
+    ArchiveGap { bytes: u64 },
+    /// The points archive starts `bytes` before the end of the pics archive.
+    ///
+    /// This usually means that the boundary between the two archives was
+    /// miscomputed, e.g. because of a corrupt EOCD comment length. The points
+    /// archive is read from its derived start instead, and like for
+    /// [`SuspiciousEocdComment`](Self::SuspiciousEocdComment), the file is
+    /// loaded without pictures if the pics archive can't be opened that way.
+    /// Like [`ArchiveGap`](Self::ArchiveGap), this is only checked with
+    /// [`ParseOptions::eager_validate`](crate::ParseOptions::eager_validate).
+    ArchiveOverlap { bytes: u64 },
+    /// `POINTS.CUP` contains neither waypoints nor tasks.
+    ///
+    /// The file is well-formed and loads normally, but this usually means that
+    /// the waypoint data got lost while exporting, e.g. if only the header row
+    /// was written.
+    EmptyWaypointData,
+    /// More warnings occurred than allowed by
+    /// [`ParseOptions::max_warnings`](crate::ParseOptions::max_warnings).
+    ///
+    /// This is always the last warning in the list. `total` is the number of
+    /// warnings that occurred, including the ones that were dropped.
+    WarningsTruncated { total: usize },
+}
+
+impl Warning {
+    /// Returns whether the warning is about the content of the CUP data.
+    ///
+    /// This covers issues reported by the CUP parser and the checks of the
+    /// parsed waypoints, as opposed to [structural](Self::is_structural)
+    /// issues of the archives.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, warnings) = CupxFile::from_path("waypoints.cupx")?;
+    /// let (cup_warnings, other_warnings): (Vec<_>, Vec<_>) =
+    ///     warnings.into_iter().partition(|warning| warning.is_cup_issue());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn is_cup_issue(&self) -> bool {
+        match self {
+            Self::CupParseIssue { .. }
+            | Self::InvalidCoordinates { .. }
+            | Self::EmptyWaypointData => true,
+            Self::NoPicturesArchive
+            | Self::SuspiciousEocdComment
+            | Self::PointsCupInPicsArchive
+            | Self::PictureFormatMismatch { .. }
+            | Self::NonUtf8PictureName { .. }
+            | Self::PicturesWithoutPrefix
+            | Self::RecoveredFromCorruptArchive
+            | Self::ArchiveGap { .. }
+            | Self::ArchiveOverlap { .. }
+            | Self::WarningsTruncated { .. } => false,
+        }
+    }
+
+    /// Returns whether the warning is about the structure of the CUPX file,
+    /// like the layout of the archives or the pictures in them.
+    ///
+    /// [`WarningsTruncated`](Self::WarningsTruncated) is neither structural
+    /// nor a [CUP issue](Self::is_cup_issue), since the dropped warnings can
+    /// be of both kinds.
+    pub fn is_structural(&self) -> bool {
+        !self.is_cup_issue() && !matches!(self, Self::WarningsTruncated { .. })
+    }
+}
+
+/// A problem found by [`CupxWriter::validate`](crate::CupxWriter::validate).
+///
+/// Issues for which [`is_error`](Self::is_error) returns `true` make writing
+/// fail, the others produce a valid CUPX file that other applications may not
+/// handle as intended.
+///
+/// New variants may be added in minor releases, so `match` statements on
+/// issues need a wildcard `_ =>` arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WriterIssue {
+    /// A picture filename is empty or contains path separators, see
+    /// [`Error::InvalidFilename`].
+    InvalidFilename(String),
+    /// The raw `POINTS.CUP` data is empty, see [`Error::EmptyRawPoints`].
+    EmptyRawPoints,
+    /// The file of a [`PictureSource::Path`](crate::PictureSource::Path) or
+    /// [`PictureSource::CupxEntry`](crate::PictureSource::CupxEntry) picture
+    /// doesn't exist or is not a regular file.
+    ///
+    /// Writing fails with an [`Error::Io`] once the file is opened.
+    #[cfg(feature = "std")]
+    MissingFile {
+        name: String,
+        path: std::path::PathBuf,
+    },
+    /// Two pictures have names that only differ in case.
+    ///
+    /// Readers usually match picture names case-insensitively, so only one of
+    /// them can be found.
+    CaseCollision { first: String, second: String },
+    /// A waypoint references a picture that is not part of the file.
+    ///
+    /// References are matched case-insensitively. This is not checked if
+    /// [raw points](crate::CupxWriter::set_raw_points) are used.
+    DanglingReference { waypoint: String, picture: String },
+}
+
+impl WriterIssue {
+    /// Returns whether the issue makes writing fail.
+    pub fn is_error(&self) -> bool {
+        match self {
+            Self::InvalidFilename(_) | Self::EmptyRawPoints => true,
+            #[cfg(feature = "std")]
+            Self::MissingFile { .. } => true,
+            Self::CaseCollision { .. } | Self::DanglingReference { .. } => false,
+        }
+    }
 }
 
 /// Errors that can occur when reading or writing CUPX files.
+///
+/// New variants may be added in minor releases, so `match` statements on
+/// errors need a wildcard `_ =>` arm.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     /// An I/O error occurred.
     #[error(transparent)]
@@ -30,10 +224,62 @@ pub enum Error {
     /// This typically means the required ZIP archive structure could not be found.
     #[error("Invalid CUPX file: could not find two ZIP archives")]
     InvalidCupx,
+    /// The input of [`CupxFile::from_gz_reader`](crate::CupxFile) is not
+    /// gzip-compressed.
+    #[error("Input is not gzip-compressed")]
+    NotGzip,
     /// A picture filename is invalid.
     ///
     /// Picture filenames must not be empty and must not contain path separators
-    /// (`/` or `\`).
+    /// (`/` or `\`). With [`CupxWriter::prefix_pictures`](crate::CupxWriter::prefix_pictures)
+    /// disabled, `/` is allowed between non-empty path components.
     #[error("Invalid picture filename: {0}")]
     InvalidFilename(String),
+    /// A supplementary points filename is invalid.
+    ///
+    /// Supplementary points filenames must end in `.cup`, must not contain path
+    /// separators and must not be `POINTS.CUP`.
+    #[error("Invalid points filename: {0}")]
+    InvalidPointsFilename(String),
+    /// A picture is larger than allowed by
+    /// [`ParseOptions::max_picture_size`](crate::ParseOptions::max_picture_size).
+    #[error("Picture {name} is too large: {size} bytes (limit: {max_size} bytes)")]
+    PictureTooLarge {
+        name: String,
+        size: u64,
+        max_size: u64,
+    },
+    /// A text file of the points archive is larger than
+    /// [`MAX_TEXT_ENTRY_SIZE`](crate::MAX_TEXT_ENTRY_SIZE).
+    #[error("Entry {name} exceeds the size limit of {max_size} bytes")]
+    EntryTooLarge { name: String, max_size: u64 },
+    /// The written CUPX file would be larger than allowed by
+    /// [`CupxWriter::max_output_size`](crate::CupxWriter::max_output_size).
+    #[error("Output exceeds the size limit of {max_size} bytes")]
+    OutputTooLarge { max_size: u64 },
+    /// The raw `POINTS.CUP` data passed to
+    /// [`CupxWriter::set_raw_points`](crate::CupxWriter::set_raw_points) is empty.
+    #[error("Raw POINTS.CUP data is empty")]
+    EmptyRawPoints,
+    /// The decoded CUP data contains U+FFFD replacement characters.
+    ///
+    /// This means that the CUP data is not valid in the detected or configured
+    /// encoding. Only returned with
+    /// [`ParseOptions::reject_replacement_chars`](crate::ParseOptions::reject_replacement_chars),
+    /// and by [`CupxFile::read_text_entry_strict`](crate::CupxFile::read_text_entry_strict)
+    /// for text files that are not valid UTF-8.
+    #[error("CUP data contains {count} replacement character(s), the encoding is probably wrong")]
+    EncodingReplacement { count: usize },
+    /// The CUP data could not be parsed on first access, so it can't be
+    /// written back.
+    ///
+    /// Only returned for files whose parsing is deferred, like with
+    /// [`ParseOptions::lazy_cup`](crate::ParseOptions::lazy_cup), by methods
+    /// like [`CupxFile::write_preserving_compression`](crate::CupxFile::write_preserving_compression)
+    /// that would otherwise replace `POINTS.CUP` with an empty file.
+    #[error("CUP data could not be parsed: {0}")]
+    DeferredCupParse(String),
+    /// A warning occurred while parsing in [strict mode](crate::ParseOptions::strict).
+    #[error("Warning in strict mode: {0:?}")]
+    Strict(Warning),
 }
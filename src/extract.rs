@@ -0,0 +1,166 @@
+use crate::error::Error;
+use crate::limited_reader::LimitedReader;
+use crate::reader::{CupxFile, is_picture_path, picture_name};
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the folder that receives pictures not referenced by any waypoint.
+const UNREFERENCED_DIR: &str = "_unreferenced";
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Extracts all pictures into one subfolder of `dir` per waypoint.
+    ///
+    /// Each waypoint with pictures gets a folder named after the waypoint, which
+    /// contains all pictures the waypoint references. Pictures referenced by
+    /// multiple waypoints are copied into each of their folders. Pictures that no
+    /// waypoint references are written to an `_unreferenced` folder. References
+    /// to pictures that don't exist in the archive are skipped.
+    ///
+    /// Characters that are not allowed in file names on common platforms are
+    /// replaced by `_` in both folder and file names. Existing files are
+    /// overwritten.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// cupx.extract_pictures_by_waypoint("gallery")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a picture cannot be read or if creating a folder or
+    /// file fails.
+    pub fn extract_pictures_by_waypoint(&mut self, dir: impl AsRef<Path>) -> Result<(), Error> {
+        let dir = dir.as_ref();
+
+        let mut extractions = Vec::new();
+        for waypoint in self.waypoints() {
+            for picture in &waypoint.pictures {
+                if let Some(path) = self.find_picture_path(picture)
+                    && let Some(name) = picture_name(&path, self.root_pictures)
+                {
+                    extractions.push((sanitize(&waypoint.name), name.to_string()));
+                }
+            }
+        }
+
+        for name in self.unreferenced_pictures() {
+            extractions.push((UNREFERENCED_DIR.to_string(), name));
+        }
+
+        for (folder, name) in extractions {
+            let folder = dir.join(folder);
+            std::fs::create_dir_all(&folder)?;
+
+            let mut file = File::create(folder.join(sanitize(&name)))?;
+            std::io::copy(&mut self.read_picture(&name)?, &mut file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Extracts the contents of both archives into `dir`, like `unzip` would.
+    ///
+    /// `POINTS.CUP` and any supplementary files of the points archive are written
+    /// to the top level of `dir`, and all pictures to a `pics/` subfolder, so the
+    /// result mirrors the logical layout of the CUPX file. The raw bytes of every
+    /// entry are written unchanged. Entries whose names would escape `dir` (e.g.
+    /// containing `..`) are skipped. Existing files are overwritten.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// cupx.explode("waypoints")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the points archive can't be read (e.g. for files
+    /// created with [`from_archives`](Self::from_archives)), if a picture cannot
+    /// be read or if creating a folder or file fails.
+    pub fn explode(&mut self, dir: impl AsRef<Path>) -> Result<(), Error> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+
+        let points_range = self.pics_boundary.unwrap_or(0)..self.file_size;
+        self.with_reader(|reader| {
+            let points_reader = LimitedReader::new(&mut *reader, points_range)?;
+            let mut points_archive = zip::ZipArchive::new(points_reader)?;
+            for index in 0..points_archive.len() {
+                let mut file = points_archive.by_index(index)?;
+                // Pictures of single-ZIP files are written below
+                if file.is_dir() || is_picture_path(file.name()) {
+                    continue;
+                }
+                if let Some(path) = enclosed_path(dir, file.name()) {
+                    std::io::copy(&mut file, &mut create_file(&path)?)?;
+                }
+            }
+            Ok(())
+        })?;
+
+        // Pictures without the `pics/` prefix are moved into the subfolder as well
+        let paths = self
+            .picture_paths()
+            .filter_map(|path| {
+                let name = picture_name(path, self.root_pictures)?;
+                Some((path.to_string(), format!("pics/{name}")))
+            })
+            .collect::<Vec<_>>();
+        for (path, target) in paths {
+            if let Some(target) = enclosed_path(dir, &target) {
+                let mut file = create_file(&target)?;
+                std::io::copy(&mut self.read_picture_by_path(&path)?, &mut file)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the path of the archive entry `name` within `dir`, or `None` if it
+/// would end up outside of `dir`.
+pub(crate) fn enclosed_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let relative = Path::new(name);
+    let is_enclosed = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    is_enclosed.then(|| dir.join(relative))
+}
+
+/// Creates the file at `path`, including any missing parent folders.
+fn create_file(path: &Path) -> Result<File, Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(File::create(path)?)
+}
+
+/// Replaces characters that are not allowed in file names with `_`.
+fn sanitize(name: &str) -> String {
+    let sanitized = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+
+    // Avoid empty names and names like `.` or `..`
+    let trimmed = sanitized.trim_matches(|c| c == '.' || c == ' ');
+    if trimmed.is_empty() {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
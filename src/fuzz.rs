@@ -0,0 +1,34 @@
+use crate::error::Error;
+use crate::options::ParseOptions;
+use crate::reader::CupxFile;
+use std::io::Cursor;
+
+impl CupxFile<Cursor<&[u8]>> {
+    /// Runs the complete read pipeline on `bytes` and discards the result.
+    ///
+    /// This is the entry point of the `cargo fuzz` target in the `fuzz/`
+    /// directory. Both archives are located and opened, the CUP data is parsed,
+    /// and every picture is decompressed and checked against its CRC-32, like
+    /// with [`ParseOptions::eager_validate`].
+    ///
+    /// Malformed input must only ever result in an `Err` and never in a panic.
+    /// Any panic found by the fuzzer is a bug.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// assert!(CupxFile::try_parse(b"PK\x05\x06 not a CUPX file").is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid CUPX file, if the CUP data
+    /// is invalid, or if a picture can't be decompressed.
+    pub fn try_parse(bytes: &[u8]) -> Result<(), Error> {
+        let options = ParseOptions::new().eager_validate(true);
+        CupxFile::from_reader_with_options(Cursor::new(bytes), options)?;
+        Ok(())
+    }
+}
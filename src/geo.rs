@@ -0,0 +1,17 @@
+use seeyou_cup::Waypoint;
+
+/// Mean radius of the earth in meters, as used for great-circle distances.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Returns the great-circle distance between two waypoints in meters.
+///
+/// This uses the haversine formula on a spherical earth, which is accurate
+/// to about 0.5% for the distances found in tasks.
+pub(crate) fn distance(from: &Waypoint, to: &Waypoint) -> f64 {
+    let (lat1, lat2) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.longitude - from.longitude).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+}
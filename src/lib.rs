@@ -1,11 +1,67 @@
 #![doc = include_str!("../README.md")]
 
+mod append;
+mod archive_stats;
+mod bounding_box;
+#[cfg(feature = "std")]
+mod build;
+#[cfg(feature = "sha2")]
+mod checksums;
+mod cup_lines;
+mod diff;
+#[cfg(feature = "sha2")]
+mod digest;
+mod eocd;
 mod error;
+#[cfg(feature = "std")]
+mod extract;
+#[cfg(feature = "fuzz")]
+mod fuzz;
+mod geo;
 mod limited_reader;
+mod limited_writer;
+mod map_pictures;
+mod normalize;
+mod options;
+mod picture_coverage;
+mod picture_format;
+mod picture_info;
+mod picture_reader;
+#[cfg(feature = "parallel")]
+mod pictures_channel;
+mod prune;
 mod reader;
+mod repack;
+mod rewrite;
+mod task_summary;
+mod text_entry;
+#[cfg(feature = "std")]
+mod verify;
+mod warning_list;
 mod writer;
 
-pub use error::{Error, Warning};
+pub use archive_stats::ArchiveStats;
+pub use bounding_box::BoundingBox;
+#[cfg(feature = "std")]
+pub use build::build;
+#[cfg(feature = "sha2")]
+pub use checksums::ChecksumResult;
+pub use diff::CupxDiff;
+pub use eocd::{Eocd, parse_eocd};
+pub use error::{Error, Warning, WriterIssue};
+pub use options::{DEFAULT_MAX_WARNINGS, ParseOptions};
+pub use picture_coverage::PictureCoverage;
+pub use picture_format::PictureFormat;
+pub use picture_info::PictureInfo;
+pub use picture_reader::PictureReader;
+pub use prune::PruneStats;
 pub use reader::CupxFile;
+pub use repack::{RepackStats, repack};
 pub use seeyou_cup as cup;
-pub use writer::{CupxWriter, PictureSource};
+pub use task_summary::TaskSummary;
+pub use text_entry::MAX_TEXT_ENTRY_SIZE;
+#[cfg(feature = "std")]
+pub use verify::PictureMismatch;
+pub use writer::{
+    CupxLayout, CupxStreamWriter, CupxWriter, DEFAULT_PREVIEW_NAME, PictureOrder, PictureSource,
+};
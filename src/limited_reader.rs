@@ -1,4 +1,4 @@
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
 use std::ops::{Bound, RangeBounds};
 
 /// A reader wrapper that restricts access to a specific byte range of the underlying reader.
@@ -9,6 +9,10 @@ use std::ops::{Bound, RangeBounds};
 ///
 /// The reader translates all operations to work within the specified range, making it
 /// appear to consumers as if only that portion of the data exists.
+///
+/// All offset arithmetic is checked. Operations that would overflow a `u64` return an
+/// [`InvalidInput`](ErrorKind::InvalidInput) error instead of panicking.
+#[derive(Clone)]
 pub struct LimitedReader<R, B: RangeBounds<u64>> {
     inner: R,
     range: B,
@@ -17,11 +21,7 @@ pub struct LimitedReader<R, B: RangeBounds<u64>> {
 
 impl<R: Read + Seek, B: RangeBounds<u64>> LimitedReader<R, B> {
     pub fn new(mut inner: R, range: B) -> std::io::Result<Self> {
-        let start = match range.start_bound() {
-            Bound::Included(&n) => n,
-            Bound::Excluded(&n) => n + 1,
-            Bound::Unbounded => 0,
-        };
+        let start = start_offset(&range)?;
 
         inner.seek(SeekFrom::Start(start))?;
 
@@ -39,13 +39,7 @@ impl<R: Read + Seek, B: RangeBounds<u64>> LimitedReader<R, B> {
 
 impl<R: Read + Seek, B: RangeBounds<u64>> Read for LimitedReader<R, B> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let end_bound = match self.range.end_bound() {
-            Bound::Excluded(&n) => Some(n),
-            Bound::Included(&n) => Some(n + 1),
-            Bound::Unbounded => None,
-        };
-
-        let to_read = if let Some(end) = end_bound {
+        let to_read = if let Some(end) = end_offset(&self.range)? {
             if self.pos >= end {
                 return Ok(0);
             }
@@ -56,47 +50,33 @@ impl<R: Read + Seek, B: RangeBounds<u64>> Read for LimitedReader<R, B> {
         };
 
         let n = self.inner.read(&mut buf[..to_read])?;
-        self.pos += n as u64;
+        self.pos = self.pos.checked_add(n as u64).ok_or_else(overflow)?;
         Ok(n)
     }
 }
 
 impl<R: Read + Seek, B: RangeBounds<u64>> Seek for LimitedReader<R, B> {
     fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        let start = match self.range.start_bound() {
-            Bound::Included(&n) => n,
-            Bound::Excluded(&n) => n + 1,
-            Bound::Unbounded => 0,
-        };
+        let start = start_offset(&self.range)?;
+        let end = end_offset(&self.range)?;
 
         let new_pos = match pos {
-            SeekFrom::Start(offset) => start + offset,
-            SeekFrom::Current(offset) => {
-                if offset >= 0 {
-                    self.pos + offset as u64
-                } else {
-                    self.pos.saturating_sub((-offset) as u64)
-                }
-            }
+            SeekFrom::Start(offset) => start.checked_add(offset).ok_or_else(overflow)?,
+            SeekFrom::Current(offset) => apply_offset(self.pos, offset)?,
             SeekFrom::End(offset) => {
-                let end = match self.range.end_bound() {
-                    Bound::Excluded(&end) => end,
-                    Bound::Included(&end) => end + 1,
-                    Bound::Unbounded => self.inner.seek(SeekFrom::End(0))?,
+                let end = match end {
+                    Some(end) => end,
+                    None => self.inner.seek(SeekFrom::End(0))?,
                 };
 
-                if offset >= 0 {
-                    end + offset as u64
-                } else {
-                    end.saturating_sub((-offset) as u64)
-                }
+                apply_offset(end, offset)?
             }
         };
 
-        let clamped = match self.range.end_bound() {
-            Bound::Excluded(&end) => new_pos.clamp(start, end),
-            Bound::Included(&end) => new_pos.clamp(start, end + 1),
-            Bound::Unbounded => new_pos.max(start),
+        let clamped = match end {
+            // `max()` guards against `clamp()` panicking on inverted ranges
+            Some(end) => new_pos.clamp(start, end.max(start)),
+            None => new_pos.max(start),
         };
 
         self.inner.seek(SeekFrom::Start(clamped))?;
@@ -104,3 +84,100 @@ impl<R: Read + Seek, B: RangeBounds<u64>> Seek for LimitedReader<R, B> {
         Ok(clamped - start)
     }
 }
+
+/// Returns the first offset included in the range.
+fn start_offset<B: RangeBounds<u64>>(range: &B) -> std::io::Result<u64> {
+    match range.start_bound() {
+        Bound::Included(&n) => Ok(n),
+        Bound::Excluded(&n) => n.checked_add(1).ok_or_else(overflow),
+        Bound::Unbounded => Ok(0),
+    }
+}
+
+/// Returns the first offset after the range, or `None` if the range is unbounded.
+fn end_offset<B: RangeBounds<u64>>(range: &B) -> std::io::Result<Option<u64>> {
+    match range.end_bound() {
+        Bound::Excluded(&n) => Ok(Some(n)),
+        Bound::Included(&n) => n.checked_add(1).map(Some).ok_or_else(overflow),
+        Bound::Unbounded => Ok(None),
+    }
+}
+
+/// Applies a signed offset to a position, saturating at zero for negative offsets.
+fn apply_offset(pos: u64, offset: i64) -> std::io::Result<u64> {
+    if offset >= 0 {
+        pos.checked_add(offset as u64).ok_or_else(overflow)
+    } else {
+        Ok(pos.saturating_sub(offset.unsigned_abs()))
+    }
+}
+
+fn overflow() -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidInput, "offset overflows u64")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_excluded_start_overflow() {
+        let range = (Bound::Excluded(u64::MAX), Bound::Unbounded);
+        let result = LimitedReader::new(Cursor::new(vec![0u8; 10]), range);
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_included_end_overflow() {
+        let mut reader = LimitedReader::new(Cursor::new(vec![0u8; 10]), 0..=u64::MAX).unwrap();
+        let error = reader.read(&mut [0u8; 4]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_seek_extreme_offsets() {
+        let data = (0..100).collect::<Vec<u8>>();
+
+        for range_start in [0, 1, 50, 99, 100, 1000, u64::MAX] {
+            let Ok(mut reader) = LimitedReader::new(Cursor::new(data.clone()), range_start..)
+            else {
+                continue;
+            };
+
+            for pos in [
+                SeekFrom::Start(0),
+                SeekFrom::Start(u64::MAX),
+                SeekFrom::Current(i64::MAX),
+                SeekFrom::Current(i64::MIN),
+                SeekFrom::End(i64::MAX),
+                SeekFrom::End(i64::MIN),
+                SeekFrom::End(-1),
+            ] {
+                // Must not panic, but may return an error
+                let _ = reader.seek(pos);
+                let _ = reader.read(&mut [0u8; 16]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_seek_inverted_range() {
+        #[allow(clippy::reversed_empty_ranges)]
+        let mut reader = LimitedReader::new(Cursor::new(vec![0u8; 100]), 50..10).unwrap();
+        assert_eq!(reader.seek(SeekFrom::End(0)).unwrap(), 0);
+        assert_eq!(reader.read(&mut [0u8; 16]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_seek_overflow() {
+        let mut reader = LimitedReader::new(Cursor::new(vec![0u8; 100]), 10..).unwrap();
+        let error = reader.seek(SeekFrom::Start(u64::MAX)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        reader.seek(SeekFrom::Start(5)).unwrap();
+        let mut buffer = [0u8; 1];
+        reader.read_exact(&mut buffer).unwrap();
+        assert_eq!(reader.stream_position().unwrap(), 6);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::error::Error;
+use std::io::{Seek, SeekFrom, Write};
+
+/// A writer wrapper that fails once the output would grow beyond a size limit.
+///
+/// This is used by [`CupxWriter`](crate::CupxWriter) to abort writing as soon
+/// as a CUPX file exceeds its configured maximum size, instead of producing the
+/// complete file first. The size is measured from the position of the
+/// underlying writer when the wrapper was created, so seeking back to patch
+/// ZIP headers doesn't count twice.
+pub struct LimitedWriter<W> {
+    inner: W,
+    start: u64,
+    pos: u64,
+    max_size: Option<u64>,
+    exceeded: bool,
+}
+
+impl<W: Write> LimitedWriter<W> {
+    /// Wraps `inner`, whose current position is `start`.
+    pub fn new(inner: W, start: u64, max_size: Option<u64>) -> Self {
+        Self {
+            inner,
+            start,
+            pos: start,
+            max_size,
+            exceeded: false,
+        }
+    }
+}
+
+impl<W: Write> Write for LimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // `ZipWriter` tries to finish the archive when it is dropped after an
+        // error, and logs to stderr if that fails. Once the limit is exceeded the
+        // output is useless anyway, so further writes are silently discarded.
+        if self.exceeded {
+            return Ok(buf.len());
+        }
+
+        if let Some(max_size) = self.max_size {
+            let end = self.pos.saturating_add(buf.len() as u64);
+            if end.saturating_sub(self.start) > max_size {
+                self.exceeded = true;
+                return Err(std::io::Error::other(SizeLimitExceeded(max_size)));
+            }
+        }
+
+        let n = self.inner.write(buf)?;
+        self.pos = self.pos.saturating_add(n as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write + Seek> Seek for LimitedWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.pos = self.inner.seek(pos)?;
+        Ok(self.pos)
+    }
+}
+
+/// The I/O error payload used when a [`LimitedWriter`] exceeds its limit.
+#[derive(Debug)]
+struct SizeLimitExceeded(u64);
+
+impl std::fmt::Display for SizeLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "output exceeds {} bytes", self.0)
+    }
+}
+
+impl std::error::Error for SizeLimitExceeded {}
+
+/// Converts the I/O error of an exceeded [`LimitedWriter`] into
+/// [`Error::OutputTooLarge`], passing all other errors through.
+pub fn map_size_limit_error(error: Error) -> Error {
+    let io_error = match &error {
+        Error::Io(io_error) | Error::Zip(zip::result::ZipError::Io(io_error)) => io_error,
+        _ => return error,
+    };
+
+    match io_error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<SizeLimitExceeded>())
+    {
+        Some(SizeLimitExceeded(max_size)) => Error::OutputTooLarge {
+            max_size: *max_size,
+        },
+        None => error,
+    }
+}
@@ -0,0 +1,80 @@
+use crate::error::Error;
+use crate::reader::{CupxFile, check_picture_size, picture_name, read_sized};
+use crate::writer::{default_file_options, sized_file_options};
+use std::io::{Read, Seek, Write};
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Writes a copy of this file with every picture transformed by `f`.
+    ///
+    /// `f` receives the filename (without the `pics/` prefix) and the content of
+    /// each picture, and returns the new content, which is stored under the same
+    /// name. If it returns an empty `Vec`, the picture is dropped. This is useful
+    /// for batch operations like stripping metadata or re-encoding images.
+    ///
+    /// Pictures are decompressed one at a time, and the new content is
+    /// compressed like in [`CupxWriter`](crate::CupxWriter). All other entries
+    /// are copied without recompressing them, and the points archive is copied
+    /// unchanged, so picture references in `POINTS.CUP` are not updated for
+    /// dropped pictures.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// // Drop all pictures larger than 1 MB
+    /// cupx.map_pictures(
+    ///     |_name, data| Ok(if data.len() > 1_000_000 { Vec::new() } else { data }),
+    ///     File::create("small.cupx")?,
+    /// )?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error returned by `f`, or an error if a picture exceeds
+    /// [`ParseOptions::max_picture_size`](crate::ParseOptions::max_picture_size)
+    /// or if reading from the source or writing to the output fails.
+    pub fn map_pictures<W: Write + Seek>(
+        &mut self,
+        mut f: impl FnMut(&str, Vec<u8>) -> Result<Vec<u8>, Error>,
+        out: W,
+    ) -> Result<(), Error> {
+        let root_pictures = self.root_pictures;
+        let max_picture_size = self.max_picture_size;
+        let options = default_file_options();
+        let mut pics_zip = zip::ZipWriter::new(out);
+
+        if let Some(pics_archive) = self.pics_archive.as_mut() {
+            for index in 0..pics_archive.len() {
+                let mut entry = pics_archive.by_index(index)?;
+                let path = entry.name().to_string();
+                let Some(name) = picture_name(&path, root_pictures).filter(|_| !entry.is_dir())
+                else {
+                    drop(entry);
+                    pics_zip.raw_copy_file(pics_archive.by_index_raw(index)?)?;
+                    continue;
+                };
+
+                check_picture_size(&entry, max_picture_size)?;
+                let size = entry.size();
+                let data = read_sized(&mut entry, size)?;
+
+                let data = f(name, data)?;
+                if !data.is_empty() {
+                    let options = sized_file_options(options, data.len() as u64);
+                    pics_zip.start_file(path.as_str(), options)?;
+                    pics_zip.write_all(&data)?;
+                }
+            }
+        }
+
+        let mut out = pics_zip.finish()?;
+
+        self.copy_points_archive(&mut out)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use crate::error::Error;
+use crate::picture_format::PictureFormat;
+use crate::reader::{CupxFile, format_mismatch, picture_name};
+use crate::rewrite::is_points_cup;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek, Write};
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Writes a copy of this file with picture extensions matching their content.
+    ///
+    /// Every picture whose content doesn't match its extension (see
+    /// [`Warning::PictureFormatMismatch`](crate::Warning::PictureFormatMismatch))
+    /// is renamed to the usual extension of its detected format, e.g. a PNG
+    /// image stored as `photo.jpg` becomes `photo.png`. Waypoint references to
+    /// renamed pictures are updated accordingly.
+    ///
+    /// Pictures are copied without recompressing them, and `POINTS.CUP` is
+    /// rewritten from the updated CUP data like in
+    /// [`write_preserving_compression`](Self::write_preserving_compression). Pictures are not renamed if the new
+    /// name is already taken by another picture.
+    ///
+    /// Returns the applied renames as `(old_name, new_name)` pairs, without the
+    /// `pics/` prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let renames = cupx.normalize_picture_extensions(File::create("fixed.cupx")?)?;
+    /// for (old_name, new_name) in renames {
+    ///     println!("{old_name} -> {new_name}");
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::DeferredCupParse`] if the CUP data was parsed lazily
+    /// and parsing failed, or an error if reading from the source or writing
+    /// to the output fails.
+    pub fn normalize_picture_extensions(
+        &mut self,
+        out: impl Write + Seek,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let names = self.picture_names().collect::<Vec<_>>();
+        let mut taken = names
+            .iter()
+            .map(|name| name.to_lowercase())
+            .collect::<HashSet<_>>();
+
+        let mut renames = Vec::new();
+        for name in &names {
+            let mut header = Vec::with_capacity(PictureFormat::HEADER_LEN);
+            self.read_picture(name)?
+                .take(PictureFormat::HEADER_LEN as u64)
+                .read_to_end(&mut header)?;
+
+            let Some((extension, detected)) = format_mismatch(name, &header) else {
+                continue;
+            };
+
+            let stem = &name[..name.len() - extension.len()];
+            let new_name = format!("{stem}{}", detected.extension());
+            if taken.insert(new_name.to_lowercase()) {
+                renames.push((name.clone(), new_name));
+            }
+        }
+
+        let mut cup_file = self.checked_cup_file()?.clone();
+        let renamed = renames
+            .iter()
+            .map(|(old_name, new_name)| (old_name.to_lowercase(), new_name.as_str()))
+            .collect::<HashMap<_, _>>();
+        for waypoint in &mut cup_file.waypoints {
+            for picture in &mut waypoint.pictures {
+                if let Some(new_name) = renamed.get(&picture.to_lowercase()) {
+                    *picture = new_name.to_string();
+                }
+            }
+        }
+
+        let mut cup_data = Vec::new();
+        cup_file.to_writer(&mut cup_data)?;
+
+        let single_zip = self.single_zip;
+        let root_pictures = self.root_pictures;
+        let mut pics_zip = zip::ZipWriter::new(out);
+
+        if let Some(pics_archive) = self.pics_archive.as_mut() {
+            for index in 0..pics_archive.len() {
+                let entry = pics_archive.by_index_raw(index)?;
+                let name = entry.name().to_string();
+
+                // Written by `rewrite_points_archive()`
+                if single_zip && is_points_cup(&name) {
+                    continue;
+                }
+
+                let renaming = picture_name(&name, root_pictures)
+                    .filter(|_| !entry.is_dir())
+                    .and_then(|old_name| Some((old_name, renamed.get(&old_name.to_lowercase())?)));
+                match renaming {
+                    Some((old_name, new_name)) => {
+                        let prefix = &name[..name.len() - old_name.len()];
+                        pics_zip.raw_copy_file_rename(entry, format!("{prefix}{new_name}"))?
+                    }
+                    None => pics_zip.raw_copy_file(entry)?,
+                }
+            }
+        }
+
+        self.rewrite_points_archive(pics_zip, &cup_data)?;
+
+        Ok(renames)
+    }
+}
@@ -0,0 +1,165 @@
+use seeyou_cup::Encoding;
+
+/// Default of [`ParseOptions::max_warnings`].
+///
+/// This is far more than normal files produce, but keeps the memory used for
+/// warnings bounded for malicious files with millions of broken lines.
+pub const DEFAULT_MAX_WARNINGS: usize = 10_000;
+
+/// Options for parsing a CUPX file.
+///
+/// Used with [`CupxFile::from_reader_with_options`](crate::CupxFile::from_reader_with_options).
+/// The default options detect the encoding automatically, collect up to
+/// [`DEFAULT_MAX_WARNINGS`] warnings and don't limit the size of pictures.
+///
+/// # Examples
+///
+/// ```no_run
+/// use seeyou_cupx::{CupxFile, ParseOptions};
+/// use std::fs::File;
+///
+/// let options = ParseOptions::new()
+///     .max_warnings(10)
+///     .max_picture_size(10 * 1024 * 1024);
+/// let (cupx, warnings) = CupxFile::from_reader_with_options(File::open("waypoints.cupx")?, options)?;
+/// # Ok::<(), seeyou_cupx::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub(crate) encoding: Option<Encoding>,
+    pub(crate) max_picture_size: Option<u64>,
+    pub(crate) strict: bool,
+    pub(crate) max_warnings: Option<usize>,
+    pub(crate) eager_validate: bool,
+    pub(crate) lazy_cup: bool,
+    pub(crate) search_chunk_size: Option<usize>,
+    pub(crate) recover: bool,
+    pub(crate) reject_replacement_chars: bool,
+}
+
+impl ParseOptions {
+    /// Creates the default parse options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the text encoding of the CUP file.
+    ///
+    /// By default, the encoding is detected automatically.
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Sets the maximum uncompressed size of a single picture in bytes.
+    ///
+    /// Reading a larger picture returns [`Error::PictureTooLarge`](crate::Error::PictureTooLarge)
+    /// instead of decompressing it. This protects against decompression bombs in
+    /// untrusted files.
+    pub fn max_picture_size(mut self, max_picture_size: u64) -> Self {
+        self.max_picture_size = Some(max_picture_size);
+        self
+    }
+
+    /// Enables strict mode, in which any warning is turned into an error.
+    ///
+    /// Parsing then fails with [`Error::Strict`](crate::Error::Strict) containing
+    /// the first warning, regardless of [`max_warnings`](Self::max_warnings).
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the maximum number of warnings to return.
+    ///
+    /// If more warnings occur, only the first `max_warnings` are returned, followed
+    /// by a [`Warning::WarningsTruncated`](crate::Warning::WarningsTruncated). The
+    /// dropped warnings are never stored, so this also bounds the memory used for
+    /// warnings on malicious input.
+    ///
+    /// Defaults to [`DEFAULT_MAX_WARNINGS`]. Use `usize::MAX` to keep all
+    /// warnings.
+    pub fn max_warnings(mut self, max_warnings: usize) -> Self {
+        self.max_warnings = Some(max_warnings);
+        self
+    }
+
+    /// Returns the configured warning limit, or the default.
+    pub(crate) fn warning_limit(&self) -> usize {
+        self.max_warnings.unwrap_or(DEFAULT_MAX_WARNINGS)
+    }
+
+    /// Enables decompressing all pictures while parsing to verify their checksums.
+    ///
+    /// By default, pictures are only decompressed when they are read, so corrupt
+    /// pictures are not noticed until then. With eager validation, parsing fails
+    /// if any picture is corrupt.
+    ///
+    /// Eager validation also reports pictures whose content doesn't match their
+    /// file extension as [`Warning::PictureFormatMismatch`](crate::Warning::PictureFormatMismatch).
+    pub fn eager_validate(mut self, eager_validate: bool) -> Self {
+        self.eager_validate = eager_validate;
+        self
+    }
+
+    /// Enables deferring parsing of the CUP data until it is first accessed.
+    ///
+    /// Only the raw `POINTS.CUP` data is read while parsing. It is parsed on the
+    /// first call to [`CupxFile::cup_file`](crate::CupxFile::cup_file),
+    /// [`waypoints`](crate::CupxFile::waypoints) or [`tasks`](crate::CupxFile::tasks),
+    /// which speeds up workflows that only need the pictures of many files.
+    ///
+    /// Warnings and errors from parsing the CUP data are then not returned by the
+    /// constructor, but by [`CupxFile::deferred_warnings`](crate::CupxFile::deferred_warnings).
+    /// They are also not subject to [`strict`](Self::strict) mode, and are limited
+    /// to the larger of [`max_warnings`](Self::max_warnings) and
+    /// [`DEFAULT_MAX_WARNINGS`].
+    pub fn lazy_cup(mut self, lazy_cup: bool) -> Self {
+        self.lazy_cup = lazy_cup;
+        self
+    }
+
+    /// Sets the size of the chunks read while searching for the ZIP archives.
+    ///
+    /// The archives are located by reading the file backwards in chunks of this
+    /// size, 64 KiB by default. Larger chunks reduce the number of reads on slow
+    /// network filesystems. Chunks are never larger than the file itself, and
+    /// sizes below 22 bytes (the size of an EOCD record) are rounded up.
+    pub fn search_chunk_size(mut self, search_chunk_size: usize) -> Self {
+        self.search_chunk_size = Some(search_chunk_size);
+        self
+    }
+
+    /// Enables salvaging the CUP data from a damaged points archive.
+    ///
+    /// If the central directory of the points archive is missing or corrupt,
+    /// the file is scanned for the local header of `POINTS.CUP` instead, and
+    /// the entry is read directly from there. Pictures are still loaded if the
+    /// pics archive is intact. A successful recovery is reported as
+    /// [`Warning::RecoveredFromCorruptArchive`](crate::Warning::RecoveredFromCorruptArchive).
+    ///
+    /// The scanned part of the file is read into memory, and entries written
+    /// with data descriptors (see
+    /// [`CupxWriter::write_streaming`](crate::CupxWriter::write_streaming))
+    /// can't be recovered.
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    /// Rejects CUP data that decodes with replacement characters.
+    ///
+    /// Bytes that are invalid in the detected or configured
+    /// [encoding](Self::encoding) are decoded as U+FFFD. With this option,
+    /// such files fail with [`Error::EncodingReplacement`](crate::Error::EncodingReplacement)
+    /// instead of silently containing garbled text. Only the waypoint and task
+    /// fields are checked.
+    ///
+    /// If parsing is [deferred](Self::lazy_cup), the error is reported as a
+    /// [`Warning::CupParseIssue`](crate::Warning::CupParseIssue) in
+    /// [`CupxFile::deferred_warnings`](crate::CupxFile::deferred_warnings) instead.
+    pub fn reject_replacement_chars(mut self, reject: bool) -> Self {
+        self.reject_replacement_chars = reject;
+        self
+    }
+}
@@ -0,0 +1,21 @@
+/// How the pictures of a CUPX file line up with the picture references of its
+/// waypoints.
+///
+/// Returned by [`CupxFile::picture_coverage`](crate::CupxFile::picture_coverage).
+/// Picture references are matched case-insensitively, and all names are
+/// without the `pics/` prefix.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PictureCoverage {
+    /// Number of pictures in the pics archive.
+    pub stored: usize,
+    /// Number of distinct pictures referenced by waypoints.
+    pub referenced: usize,
+    /// Number of distinct referenced pictures that exist in the pics archive.
+    pub referenced_and_present: usize,
+    /// Referenced pictures that don't exist in the pics archive, sorted
+    /// alphabetically.
+    pub referenced_but_missing: Vec<String>,
+    /// Pictures that are not referenced by any waypoint, sorted alphabetically.
+    pub stored_but_unreferenced: Vec<String>,
+}
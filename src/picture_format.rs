@@ -0,0 +1,71 @@
+/// The image format of a picture, as detected from its first bytes.
+///
+/// See [`Warning::PictureFormatMismatch`](crate::Warning::PictureFormatMismatch).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PictureFormat {
+    /// JPEG image, starting with `FF D8 FF`.
+    Jpeg,
+    /// PNG image, starting with the `\x89PNG` signature.
+    Png,
+    /// GIF image in the `GIF87a` or `GIF89a` variant.
+    Gif,
+    /// Windows bitmap, starting with `BM`.
+    Bmp,
+    /// WebP image in a `RIFF` container.
+    Webp,
+}
+
+impl PictureFormat {
+    /// Number of leading bytes needed by [`detect`](Self::detect).
+    pub(crate) const HEADER_LEN: usize = 12;
+
+    /// Detects the format from the first bytes of a picture.
+    ///
+    /// Returns `None` if the bytes don't match any of the known formats.
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Self::Jpeg)
+        } else if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else if header.starts_with(b"BM") {
+            Some(Self::Bmp)
+        } else if header.len() >= 12 && header.starts_with(b"RIFF") && &header[8..12] == b"WEBP" {
+            Some(Self::Webp)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the format that a file extension (without the leading dot)
+    /// stands for, matched case-insensitively.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" | "jpe" => Some(Self::Jpeg),
+            "png" => Some(Self::Png),
+            "gif" => Some(Self::Gif),
+            "bmp" => Some(Self::Bmp),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the format is already compressed, so that deflating it
+    /// again is not worthwhile.
+    pub(crate) fn is_compressed(self) -> bool {
+        !matches!(self, Self::Bmp)
+    }
+
+    /// Returns the usual file extension of the format, without the leading dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Gif => "gif",
+            Self::Bmp => "bmp",
+            Self::Webp => "webp",
+        }
+    }
+}
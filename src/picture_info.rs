@@ -0,0 +1,28 @@
+use zip::CompressionMethod;
+
+/// Metadata about a single picture in a CUPX file.
+///
+/// Returned by [`CupxFile::picture`](crate::CupxFile::picture). The values are
+/// taken from the ZIP headers, so looking them up does not decompress anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PictureInfo {
+    /// Uncompressed size of the picture in bytes.
+    pub size: u64,
+    /// Size of the picture inside the archive in bytes.
+    pub compressed_size: u64,
+    /// CRC-32 checksum of the uncompressed picture data.
+    pub crc32: u32,
+    /// Compression method used to store the picture.
+    pub compression: CompressionMethod,
+}
+
+impl PictureInfo {
+    pub(crate) fn from_zip_file<R: std::io::Read>(file: &zip::read::ZipFile<'_, R>) -> Self {
+        Self {
+            size: file.size(),
+            compressed_size: file.compressed_size(),
+            crc32: file.crc32(),
+            compression: file.compression(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::limited_reader::LimitedReader;
+use std::io::{Read, Seek};
+use std::ops::Range;
+
+/// A reader for a single picture in a CUPX file.
+///
+/// Returned by [`CupxFile::read_picture`](crate::CupxFile::read_picture). In
+/// addition to implementing [`Read`], it exposes the uncompressed size of the
+/// picture and how many bytes have been read so far, which can be used to
+/// report progress while streaming large pictures.
+pub struct PictureReader<'a, R: Read + Seek> {
+    inner: zip::read::ZipFile<'a, LimitedReader<R, Range<u64>>>,
+    bytes_read: u64,
+}
+
+impl<'a, R: Read + Seek> PictureReader<'a, R> {
+    pub(crate) fn new(inner: zip::read::ZipFile<'a, LimitedReader<R, Range<u64>>>) -> Self {
+        Self {
+            inner,
+            bytes_read: 0,
+        }
+    }
+
+    /// Returns the uncompressed size of the picture in bytes.
+    pub fn size(&self) -> u64 {
+        self.inner.size()
+    }
+
+    /// Returns the number of uncompressed bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+}
+
+impl<R: Read + Seek> Read for PictureReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read += n as u64;
+        Ok(n)
+    }
+}
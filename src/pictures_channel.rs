@@ -0,0 +1,94 @@
+use crate::error::Error;
+use crate::reader::{CupxFile, check_picture_size, picture_name, read_sized};
+use std::io::{Read, Seek};
+use std::sync::mpsc;
+
+impl<R: Read + Seek + Clone + Send + 'static> CupxFile<R> {
+    /// Decompresses all pictures on `workers` background threads and sends them
+    /// through a channel.
+    ///
+    /// Each worker gets its own clone of the pics archive, and the pictures are
+    /// distributed between them round-robin. The channel only buffers up to
+    /// `workers` pictures, so the workers pause while the receiver falls
+    /// behind, and they stop once the receiver is dropped. Pictures arrive as
+    /// `(filename, data)` pairs without the `pics/` prefix, in no particular
+    /// order.
+    ///
+    /// Since every worker clones the reader, this works best with readers that
+    /// are cheap to clone, like `Cursor<Arc<[u8]>>`. A `workers` value of `0`
+    /// is treated as `1`.
+    ///
+    /// Errors reading individual pictures, e.g. pictures exceeding
+    /// [`ParseOptions::max_picture_size`](crate::ParseOptions::max_picture_size),
+    /// are sent through the channel, and the worker continues with its next
+    /// picture.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::io::Cursor;
+    /// use std::sync::Arc;
+    ///
+    /// let data = Arc::<[u8]>::from(std::fs::read("waypoints.cupx")?);
+    /// let (cupx, _) = CupxFile::from_reader(Cursor::new(data))?;
+    /// for result in cupx.pictures_channel(4) {
+    ///     let (name, data) = result?;
+    ///     println!("{name}: {} bytes", data.len());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn pictures_channel(
+        &self,
+        workers: usize,
+    ) -> mpsc::Receiver<Result<(String, Vec<u8>), Error>> {
+        let workers = workers.max(1);
+        let (sender, receiver) = mpsc::sync_channel(workers);
+
+        let Some(pics_archive) = self.pics_archive.as_ref() else {
+            return receiver;
+        };
+
+        let root_pictures = self.root_pictures;
+        let max_picture_size = self.max_picture_size;
+        let pictures = (0..pics_archive.len())
+            .filter_map(|index| {
+                let path = pics_archive.name_for_index(index)?;
+                let name = picture_name(path, root_pictures).filter(|_| !path.ends_with('/'))?;
+                Some((index, name.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        for worker in 0..workers {
+            let mut pics_archive = pics_archive.clone();
+            let sender = sender.clone();
+            let pictures = pictures
+                .iter()
+                .skip(worker)
+                .step_by(workers)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            std::thread::spawn(move || {
+                for (index, name) in pictures {
+                    let result =
+                        pics_archive
+                            .by_index(index)
+                            .map_err(Error::from)
+                            .and_then(|file| {
+                                check_picture_size(&file, max_picture_size)?;
+                                let size = file.size();
+                                Ok((name, read_sized(file, size)?))
+                            });
+
+                    // The receiver was dropped, so nobody is interested anymore
+                    if sender.send(result).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        receiver
+    }
+}
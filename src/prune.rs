@@ -0,0 +1,75 @@
+use crate::error::Error;
+use crate::reader::{CupxFile, picture_name};
+use std::collections::HashSet;
+use std::io::{Read, Seek, Write};
+
+/// Statistics about a [`CupxFile::prune_unreferenced`] operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PruneStats {
+    /// Number of pictures that were dropped.
+    pub pictures_removed: usize,
+    /// Number of bytes the output is smaller than the source CUPX file.
+    pub bytes_removed: u64,
+}
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Writes a copy of this file without the pictures that no waypoint references.
+    ///
+    /// All remaining entries are copied without recompressing them, and the
+    /// points archive is copied unchanged. See
+    /// [`unreferenced_pictures`](Self::unreferenced_pictures) for which pictures
+    /// are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let stats = cupx.prune_unreferenced(File::create("pruned.cupx")?)?;
+    /// println!("Removed {} pictures", stats.pictures_removed);
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the source or writing to the output fails.
+    pub fn prune_unreferenced<W: Write + Seek>(&mut self, mut out: W) -> Result<PruneStats, Error> {
+        let start = out.stream_position()?;
+        let unreferenced = self
+            .unreferenced_pictures()
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let root_pictures = self.root_pictures;
+        let mut pictures_removed = 0;
+        let mut pics_zip = zip::ZipWriter::new(out);
+
+        if let Some(pics_archive) = self.pics_archive.as_mut() {
+            for index in 0..pics_archive.len() {
+                let entry = pics_archive.by_index_raw(index)?;
+                if !entry.is_dir()
+                    && picture_name(entry.name(), root_pictures)
+                        .is_some_and(|name| unreferenced.contains(name))
+                {
+                    pictures_removed += 1;
+                    continue;
+                }
+
+                pics_zip.raw_copy_file(entry)?;
+            }
+        }
+
+        let mut out = pics_zip.finish()?;
+
+        self.copy_points_archive(&mut out)?;
+
+        let new_size = out.stream_position()? - start;
+
+        Ok(PruneStats {
+            pictures_removed,
+            bytes_removed: self.file_size.saturating_sub(new_size),
+        })
+    }
+}
@@ -1,10 +1,33 @@
+use crate::archive_stats::ArchiveStats;
+use crate::bounding_box::BoundingBox;
+use crate::eocd::{Eocd, parse_eocd};
 use crate::error::{Error, Warning};
+use crate::geo;
 use crate::limited_reader::LimitedReader;
-use seeyou_cup::{CupFile, Encoding, Task, Waypoint};
-use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use crate::options::{DEFAULT_MAX_WARNINGS, ParseOptions};
+use crate::picture_coverage::PictureCoverage;
+use crate::picture_format::PictureFormat;
+use crate::picture_info::PictureInfo;
+use crate::picture_reader::PictureReader;
+use crate::task_summary::TaskSummary;
+use crate::warning_list::WarningList;
+use seeyou_cup::{CupFile, Encoding, ObservationZone, Task, Waypoint, WaypointStyle};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+#[cfg(feature = "std")]
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
 use std::ops::Range;
-use std::path::Path;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Default size of the chunks read while searching for the EOCD records.
+pub(crate) const DEFAULT_SEARCH_CHUNK_SIZE: u64 = 65536;
+
+/// The raw `POINTS.CUP` data and the options to parse it with later.
+type DeferredCup = (Vec<u8>, ParseOptions);
 
 /// A parsed CUPX file containing waypoint data and optional pictures.
 ///
@@ -15,6 +38,10 @@ use std::path::Path;
 /// The generic parameter `R` is the underlying reader type, which must implement
 /// [`Read`] and [`Seek`].
 ///
+/// If `R` implements [`Clone`], so does `CupxFile`. The parsed metadata of the
+/// pics archive is shared between clones, so cloning is cheap for readers like
+/// `Cursor<Arc<[u8]>>`, e.g. to read pictures from another thread.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -24,11 +51,46 @@ use std::path::Path;
 /// println!("Loaded {} waypoints", cupx.waypoints().len());
 /// # Ok::<(), seeyou_cupx::Error>(())
 /// ```
+#[derive(Clone)]
 pub struct CupxFile<R> {
-    cup_file: CupFile,
-    pics_archive: Option<zip::ZipArchive<LimitedReader<R, Range<u64>>>>,
+    /// The parsed CUP data, which is filled on first access if parsing is deferred.
+    cup_file: OnceLock<CupFile>,
+    /// The raw `POINTS.CUP` data and encoding, if parsing is deferred.
+    cup_data: Option<DeferredCup>,
+    /// The header line of `POINTS.CUP`.
+    cup_header: String,
+    /// Warnings from deferred parsing of the CUP data.
+    deferred_warnings: OnceLock<Vec<Warning>>,
+    /// The error message if deferred parsing of the CUP data failed.
+    deferred_error: OnceLock<String>,
+    pub(crate) pics_archive: Option<zip::ZipArchive<LimitedReader<R, Range<u64>>>>,
+    /// The underlying reader, if it is not owned by `pics_archive`.
+    reader: Option<R>,
+    /// Byte offset where the pics archive ends and the points archive begins.
+    pub(crate) pics_boundary: Option<u64>,
+    /// Whether the pictures and `POINTS.CUP` are stored in a single ZIP archive.
+    pub(crate) single_zip: bool,
+    /// Whether the pictures are stored at the top level of the pics archive
+    /// instead of in `pics/`, see [`Warning::PicturesWithoutPrefix`].
+    pub(crate) root_pictures: bool,
+    /// Metadata of all pictures, keyed by their full path in the pics archive.
+    pub(crate) picture_infos: HashMap<String, PictureInfo>,
+    /// Maximum uncompressed size of a picture that may be read.
+    pub(crate) max_picture_size: Option<u64>,
+    /// Whether picture filenames are matched exactly instead of ignoring case.
+    case_sensitive_pictures: bool,
+    /// The configured text encoding of the CUP data, if any.
+    pub(crate) encoding: Option<Encoding>,
+    /// Total size of the underlying CUPX data in bytes.
+    pub(crate) file_size: u64,
+    /// Number of entries in the points archive.
+    points_entry_count: usize,
+    /// Whether the file was assembled from separately opened archives, so that
+    /// the reader doesn't cover the complete CUPX data.
+    detached: bool,
 }
 
+#[cfg(feature = "std")]
 impl CupxFile<File> {
     /// Opens and parses a CUPX file from the given path.
     ///
@@ -53,6 +115,55 @@ impl CupxFile<File> {
         Self::from_reader(file)
     }
 
+    /// Opens and parses a CUPX file from the given path, refusing to follow a
+    /// symlink.
+    ///
+    /// This is a hardened variant of [`from_path`](Self::from_path) for paths
+    /// from untrusted sources, where a symlink could point to a sensitive file.
+    /// On Unix, the file is opened with `O_NOFOLLOW`, so opening fails if the
+    /// last component of `path` is a symlink. Symlinks in the parent
+    /// directories are still followed.
+    ///
+    /// On other platforms, the path is checked with
+    /// [`symlink_metadata`](std::fs::symlink_metadata) before opening it. This
+    /// leaves a small window in which the file could be replaced by a symlink.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, warnings) = CupxFile::from_path_no_follow("uploads/waypoints.cupx")?;
+    /// println!("Loaded {} waypoints", cupx.waypoints().len());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` is a symlink, if the file cannot be opened,
+    /// is not a valid CUPX file, or contains invalid CUP data.
+    pub fn from_path_no_follow<P: AsRef<Path>>(path: P) -> Result<(Self, Vec<Warning>), Error> {
+        let mut options = OpenOptions::new();
+        options.read(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.custom_flags(libc::O_NOFOLLOW);
+        }
+
+        #[cfg(not(unix))]
+        if std::fs::symlink_metadata(&path)?.file_type().is_symlink() {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "refusing to open a symlink",
+            )));
+        }
+
+        let file = options.open(path)?;
+        Self::from_reader(file)
+    }
+
     /// Opens and parses a CUPX file from the given path with a specific encoding.
     ///
     /// Use this when you know the encoding of the CUP file and want to avoid
@@ -69,6 +180,302 @@ impl CupxFile<File> {
         let file = File::open(path)?;
         Self::from_reader_with_encoding(file, encoding)
     }
+
+    /// Opens and parses a CUPX file from the given path, passing each warning
+    /// to `on_warning`.
+    ///
+    /// This is a variant of [`from_path`](Self::from_path) for applications that
+    /// forward warnings to their logging setup instead of collecting them. The
+    /// callback is invoked once for every warning, in the order in which they
+    /// would be returned by `from_path`, before this method returns.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let cupx = CupxFile::open("waypoints.cupx", |warning| {
+    ///     eprintln!("warning: {warning:?}");
+    /// })?;
+    /// println!("Loaded {} waypoints", cupx.waypoints().len());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened, is not a valid CUPX file,
+    /// or contains invalid CUP data. The callback is not invoked in that case.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        mut on_warning: impl FnMut(&Warning),
+    ) -> Result<Self, Error> {
+        let (cupx_file, warnings) = Self::from_path(path)?;
+        warnings.iter().for_each(&mut on_warning);
+        Ok(cupx_file)
+    }
+
+    /// Parses a CUPX file from a non-seekable reader by spooling it to a
+    /// temporary file.
+    ///
+    /// Since the ZIP archives need to be read from the end, the stream is copied
+    /// to an anonymous temporary file first, which is then parsed like a regular
+    /// file. Unlike reading the stream into memory and using
+    /// [`from_bytes`](CupxFile::from_bytes), this keeps memory usage bounded for
+    /// very large inputs. The temporary file is owned by the returned
+    /// `CupxFile` and removed by the operating system once it is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, warnings) = CupxFile::from_read_spooled(std::io::stdin())?;
+    /// println!("Loaded {} waypoints", cupx.waypoints().len());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the temporary file cannot be created or written, if
+    /// the data is not a valid CUPX file, or if the CUP data is invalid.
+    #[cfg(feature = "tempfile")]
+    pub fn from_read_spooled(mut reader: impl Read) -> Result<(Self, Vec<Warning>), Error> {
+        let mut file = tempfile::tempfile()?;
+        std::io::copy(&mut reader, &mut file)?;
+        Self::from_reader(file)
+    }
+}
+
+impl CupxFile<Cursor<Vec<u8>>> {
+    /// Parses a CUPX file from a byte slice.
+    ///
+    /// The bytes are copied into an owned buffer, so the returned `CupxFile` does
+    /// not borrow from `bytes`. The text encoding of the CUP file is detected
+    /// automatically.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let data = std::fs::read("waypoints.cupx")?;
+    /// let (cupx, warnings) = CupxFile::from_bytes(&data)?;
+    /// println!("Loaded {} waypoints", cupx.waypoints().len());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid CUPX file or if the CUP
+    /// data is invalid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, Vec<Warning>), Error> {
+        Self::from_reader(Cursor::new(bytes.to_vec()))
+    }
+
+    /// Parses a gzip-compressed CUPX file (`.cupx.gz`) from a reader.
+    ///
+    /// Since the ZIP archives need to be read from the end, the whole stream is
+    /// decompressed into memory first. This allows parsing such files directly
+    /// from non-seekable sources like HTTP responses.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    ///
+    /// let (cupx, warnings) = CupxFile::from_gz_reader(File::open("waypoints.cupx.gz")?)?;
+    /// println!("Loaded {} waypoints", cupx.waypoints().len());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NotGzip`] if the input doesn't start with the gzip magic
+    /// bytes, or an error if decompressing fails, the decompressed data is not a
+    /// valid CUPX file or if the CUP data is invalid.
+    #[cfg(feature = "flate2")]
+    pub fn from_gz_reader(mut reader: impl Read) -> Result<(Self, Vec<Warning>), Error> {
+        const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+        let mut magic = [0u8; 2];
+        match reader.read_exact(&mut magic) {
+            Ok(()) if magic == GZIP_MAGIC => {}
+            Ok(()) => return Err(Error::NotGzip),
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Err(Error::NotGzip);
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        let mut decoder = flate2::read::MultiGzDecoder::new(magic.as_slice().chain(reader));
+        let mut data = Vec::new();
+        decoder.read_to_end(&mut data)?;
+
+        Self::from_reader(Cursor::new(data))
+    }
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> CupxFile<Cursor<&'a T>> {
+    /// Returns the bytes of the picture with the given filename, avoiding a copy
+    /// where possible.
+    ///
+    /// Since the CUPX data is borrowed from memory, pictures that are stored
+    /// without compression are returned as a slice into that memory. Compressed
+    /// pictures are decompressed into an owned buffer.
+    ///
+    /// Note that the CRC-32 checksum of borrowed pictures is not verified.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::io::Cursor;
+    ///
+    /// let data = std::fs::read("waypoints.cupx")?;
+    /// let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&data[..]))?;
+    /// let picture = cupx.read_picture_cow("airport.jpg")?;
+    /// println!("Picture has {} bytes", picture.len());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the picture doesn't exist or can't be decompressed.
+    pub fn read_picture_cow(&mut self, filename: &str) -> Result<Cow<'a, [u8]>, Error> {
+        let actual_path = self
+            .find_picture_path(filename)
+            .ok_or(zip::result::ZipError::FileNotFound)?;
+
+        let pics_archive = self
+            .pics_archive
+            .as_mut()
+            .ok_or(zip::result::ZipError::FileNotFound)?;
+
+        // Cloning the archive is cheap here, since it only clones the `Arc` with
+        // the archive metadata and the borrowing cursor.
+        let data = pics_archive
+            .clone()
+            .into_inner()
+            .into_inner()
+            .into_inner()
+            .as_ref();
+
+        let file = pics_archive.by_name(&actual_path)?;
+        check_picture_size(&file, self.max_picture_size)?;
+        if file.compression() == zip::CompressionMethod::Stored && !file.encrypted() {
+            let start = usize::try_from(file.data_start()).ok();
+            let len = usize::try_from(file.size()).ok();
+            let slice = start
+                .zip(len)
+                .and_then(|(start, len)| data.get(start..start.checked_add(len)?));
+
+            if let Some(slice) = slice {
+                return Ok(Cow::Borrowed(slice));
+            }
+        }
+
+        let size = file.size();
+        Ok(Cow::Owned(read_sized(file, size)?))
+    }
+}
+
+/// Parses a CUPX file from a byte slice, discarding any warnings.
+///
+/// Use [`CupxFile::from_bytes`] instead if the warnings are needed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use seeyou_cupx::CupxFile;
+///
+/// let data = std::fs::read("waypoints.cupx")?;
+/// let cupx = CupxFile::try_from(&data[..])?;
+/// # Ok::<(), seeyou_cupx::Error>(())
+/// ```
+impl TryFrom<&[u8]> for CupxFile<Cursor<Vec<u8>>> {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let (cupx_file, _warnings) = Self::from_bytes(bytes)?;
+        Ok(cupx_file)
+    }
+}
+
+/// Parses a CUPX file from an open file, discarding any warnings.
+///
+/// Use [`CupxFile::from_reader`] instead if the warnings are needed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use seeyou_cupx::CupxFile;
+/// use std::fs::File;
+///
+/// let cupx = CupxFile::try_from(File::open("waypoints.cupx")?)?;
+/// # Ok::<(), seeyou_cupx::Error>(())
+/// ```
+#[cfg(feature = "std")]
+impl TryFrom<File> for CupxFile<File> {
+    type Error = Error;
+
+    fn try_from(file: File) -> Result<Self, Self::Error> {
+        let (cupx_file, _warnings) = Self::from_reader(file)?;
+        Ok(cupx_file)
+    }
+}
+
+/// Opens and parses a CUPX file from the given path, discarding any warnings.
+///
+/// Use [`CupxFile::from_path`] instead if the warnings are needed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use seeyou_cupx::CupxFile;
+/// use std::path::PathBuf;
+///
+/// let paths = vec![PathBuf::from("a.cupx"), PathBuf::from("b.cupx")];
+/// let files = paths
+///     .into_iter()
+///     .map(CupxFile::try_from)
+///     .collect::<Result<Vec<_>, _>>()?;
+/// # Ok::<(), seeyou_cupx::Error>(())
+/// ```
+#[cfg(feature = "std")]
+impl TryFrom<PathBuf> for CupxFile<File> {
+    type Error = Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        let (cupx_file, _warnings) = Self::from_path(path)?;
+        Ok(cupx_file)
+    }
+}
+
+/// Gives access to the parsed CUP data, like [`CupxFile::cup_file`].
+///
+/// This allows passing a `CupxFile` to functions that accept
+/// `impl AsRef<CupFile>`. `Deref` is deliberately not implemented, since the
+/// methods of [`CupFile`] would then mix with the ones of `CupxFile`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use seeyou_cupx::CupxFile;
+/// use seeyou_cupx::cup::CupFile;
+///
+/// fn count_tasks(cup_file: impl AsRef<CupFile>) -> usize {
+///     cup_file.as_ref().tasks.len()
+/// }
+///
+/// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+/// println!("{} tasks", count_tasks(&cupx));
+/// # Ok::<(), seeyou_cupx::Error>(())
+/// ```
+impl<R: Read + Seek> AsRef<CupFile> for CupxFile<R> {
+    fn as_ref(&self) -> &CupFile {
+        self.cup_file()
+    }
 }
 
 impl<R: Read + Seek> CupxFile<R> {
@@ -81,7 +488,7 @@ impl<R: Read + Seek> CupxFile<R> {
     /// Returns an error if the reader does not contain a valid CUPX file or
     /// if the CUP data is invalid.
     pub fn from_reader(reader: R) -> Result<(Self, Vec<Warning>), Error> {
-        Self::from_reader_inner(reader, None)
+        Self::from_reader_with_options(reader, ParseOptions::default())
     }
 
     /// Parses a CUPX file from a reader with a specific encoding.
@@ -97,7 +504,85 @@ impl<R: Read + Seek> CupxFile<R> {
         reader: R,
         encoding: Encoding,
     ) -> Result<(Self, Vec<Warning>), Error> {
-        Self::from_reader_inner(reader, Some(encoding))
+        Self::from_reader_with_options(reader, ParseOptions::new().encoding(encoding))
+    }
+
+    /// Parses a CUPX file from a reader with the given [`ParseOptions`].
+    ///
+    /// All other constructors that locate the archives themselves are shortcuts
+    /// for this method.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::{CupxFile, ParseOptions};
+    /// use std::fs::File;
+    ///
+    /// let options = ParseOptions::new().max_warnings(5);
+    /// let (cupx, warnings) = CupxFile::from_reader_with_options(File::open("waypoints.cupx")?, options)?;
+    /// for warning in &warnings {
+    ///     println!("{:?}", warning);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reader does not contain a valid CUPX file, if
+    /// the CUP data is invalid, if a warning occurs in strict mode, or if a
+    /// picture fails eager validation.
+    pub fn from_reader_with_options(
+        reader: R,
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<Warning>), Error> {
+        Self::from_reader_inner(reader, &options)
+    }
+
+    /// Parses a CUPX file from a reader without parsing the CUP data up front.
+    ///
+    /// This is useful if only the pictures are needed, since parsing large
+    /// waypoint sets is the most expensive part of opening a CUPX file. The CUP
+    /// data is parsed when [`cup_file`](Self::cup_file), [`waypoints`](Self::waypoints)
+    /// or [`tasks`](Self::tasks) is first called, and any warnings from that are
+    /// available via [`deferred_warnings`](Self::deferred_warnings).
+    ///
+    /// This is a shortcut for [`ParseOptions::lazy_cup`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    ///
+    /// let (cupx, _) = CupxFile::from_reader_pics_only(File::open("waypoints.cupx")?)?;
+    /// for name in cupx.picture_names() {
+    ///     println!("Picture: {}", name);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reader does not contain a valid CUPX file or if
+    /// the points archive doesn't contain a `POINTS.CUP` file.
+    pub fn from_reader_pics_only(reader: R) -> Result<(Self, Vec<Warning>), Error> {
+        Self::from_reader_with_options(reader, ParseOptions::new().lazy_cup(true))
+    }
+
+    /// Parses a CUPX file from a reader, salvaging the CUP data if the points
+    /// archive is damaged.
+    ///
+    /// This is a shortcut for [`ParseOptions::recover`]. If the central
+    /// directory of the points archive can't be read, the `POINTS.CUP` entry is
+    /// located by its local header instead, and
+    /// [`Warning::RecoveredFromCorruptArchive`] is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no readable `POINTS.CUP` entry could be found or if
+    /// the CUP data is invalid.
+    pub fn from_reader_recover(reader: R) -> Result<(Self, Vec<Warning>), Error> {
+        Self::from_reader_with_options(reader, ParseOptions::new().recover(true))
     }
 
     /// Parses a CUPX file by locating the two ZIP archives within it.
@@ -108,11 +593,17 @@ impl<R: Read + Seek> CupxFile<R> {
     /// the file contains no pictures.
     fn from_reader_inner(
         mut reader: R,
-        encoding: Option<Encoding>,
+        options: &ParseOptions,
     ) -> Result<(Self, Vec<Warning>), Error> {
-        const EOCD_SIGNATURE: &[u8] = b"PK\x05\x06";
-        const EOCD_MIN_SIZE: u64 = 22;
-        const CHUNK_SIZE: u64 = 65536; // 64KB chunks for incremental search
+        const EOCD_MIN_SIZE: u64 = Eocd::MIN_SIZE as u64;
+        // Consecutive chunks overlap by this much, so that signatures spanning two
+        // chunks are still found
+        const CHUNK_OVERLAP: u64 = Eocd::SIGNATURE.len() as u64 - 1;
+
+        let chunk_size = options
+            .search_chunk_size
+            .map_or(DEFAULT_SEARCH_CHUNK_SIZE, |size| size as u64)
+            .max(EOCD_MIN_SIZE);
 
         // Get file size
         reader.seek(SeekFrom::Start(0))?;
@@ -121,165 +612,1438 @@ impl<R: Read + Seek> CupxFile<R> {
         // Find both EOCD signatures by searching backwards incrementally
         let mut last_eocd: Option<u64> = None;
         let mut second_last_eocd: Option<u64> = None;
+        // Start of the ZIP64 records in front of the last EOCD. Signatures in
+        // there belong to the points archive and don't mark another archive.
+        let mut points_trailer_start = file_size;
         let mut search_end = file_size;
 
         // Search backwards in chunks until we find 2 EOCDs or reach the beginning
         while second_last_eocd.is_none() && search_end > 0 {
-            let chunk_size = CHUNK_SIZE.min(search_end);
+            let chunk_size = chunk_size.min(search_end);
             let chunk_start = search_end - chunk_size;
 
             reader.seek(SeekFrom::Start(chunk_start))?;
             let mut chunk_buffer = vec![0u8; chunk_size as usize];
             reader.read_exact(&mut chunk_buffer)?;
 
-            // Find the last two EOCDs in this chunk
-            // Since we iterate forward, the last ones we see are the rightmost
-            let mut chunk_last: Option<u64> = None;
-            let mut chunk_second_last: Option<u64> = None;
-
-            for offset in memchr::memmem::find_iter(&chunk_buffer, EOCD_SIGNATURE) {
-                chunk_second_last = chunk_last;
-                chunk_last = Some(chunk_start + offset as u64);
-            }
-
-            // Update global tracking: first chunk provides both, subsequent chunks provide second-to-last
-            if last_eocd.is_none() {
-                last_eocd = chunk_last;
-                second_last_eocd = chunk_second_last;
-            } else if second_last_eocd.is_none() && chunk_last.is_some() {
-                second_last_eocd = chunk_last;
+            // Visit the EOCDs in this chunk from right to left. The first one ever
+            // found belongs to the points archive, the next one outside of its
+            // ZIP64 records to the pics archive.
+            for offset in memchr::memmem::rfind_iter(&chunk_buffer, Eocd::SIGNATURE) {
+                let offset = chunk_start + offset as u64;
+                if last_eocd.is_none() {
+                    last_eocd = Some(offset);
+                    points_trailer_start = zip64_records_start(&mut reader, offset, chunk_size)?;
+                } else if offset < points_trailer_start {
+                    second_last_eocd = Some(offset);
+                    break;
+                }
             }
 
-            search_end = chunk_start;
+            search_end = match chunk_start {
+                0 => 0,
+                _ => chunk_start + CHUNK_OVERLAP,
+            };
         }
 
-        let mut warnings = Vec::new();
+        let mut warnings = WarningList::new(options.warning_limit());
+        let mut suspicious_comment = false;
 
         // Determine points archive range and whether pics exist
-        let pics_boundary = if let Some(first_eocd_offset) = second_last_eocd {
+        let mut pics_boundary = if let Some(first_eocd_offset) = second_last_eocd {
             // Two ZIP archives found (normal case with pictures)
-            // Calculate the boundary: first EOCD offset + EOCD record length
-            // Read comment length from first EOCD to get full record size
-            reader.seek(SeekFrom::Start(first_eocd_offset + 20))?;
-            let mut comment_len_buf = [0u8; 2];
-            reader.read_exact(&mut comment_len_buf)?;
-            let comment_len = u16::from_le_bytes(comment_len_buf) as u64;
-
-            let boundary = first_eocd_offset + EOCD_MIN_SIZE + comment_len;
-            Some(boundary)
+            // Calculate the boundary: first EOCD offset + EOCD record length,
+            // which includes the comment
+            reader.seek(SeekFrom::Start(first_eocd_offset))?;
+            let mut record = [0u8; Eocd::MIN_SIZE];
+            reader.read_exact(&mut record)?;
+            let eocd = parse_eocd(&record).ok_or(Error::InvalidCupx)?;
+
+            let boundary = first_eocd_offset.saturating_add(eocd.record_size());
+
+            // A corrupt comment length could push the boundary into the points
+            // archive. In that case, assume there is no comment at all.
+            if boundary > file_size || boundary > points_trailer_start {
+                warnings.push(Warning::SuspiciousEocdComment);
+                suspicious_comment = true;
+                Some(first_eocd_offset + EOCD_MIN_SIZE)
+            } else {
+                Some(boundary)
+            }
         } else if last_eocd.is_some() {
-            // Only one ZIP archive found (no pictures)
-            warnings.push(Warning::NoPicturesArchive);
+            // Only one ZIP archive found (no pictures, or single-ZIP layout)
             None
+        } else if options.recover {
+            return Self::from_recovered(reader, None, file_size, options, warnings);
         } else {
             return Err(Error::InvalidCupx);
         };
 
+        if options.eager_validate
+            && let (Some(boundary), Some(points_eocd_offset)) = (pics_boundary, last_eocd)
+            && let Some(warning) = check_archive_boundary(
+                &mut reader,
+                points_eocd_offset,
+                points_trailer_start,
+                boundary,
+            )?
+        {
+            // The points archive can't be opened from a boundary inside of it, so
+            // treat this like a corrupt comment length of the pics archive
+            if let Warning::ArchiveOverlap { bytes } = warning {
+                pics_boundary = Some(boundary - bytes);
+                suspicious_comment = true;
+            }
+            warnings.push(warning);
+        }
+
         // Read the points archive to get the CUP file
         let points_start = pics_boundary.unwrap_or(0);
-        let points_reader = LimitedReader::new(reader, points_start..)?;
+        if options.recover && !has_points_cup(&mut reader, points_start..file_size) {
+            return Self::from_recovered(reader, pics_boundary, file_size, options, warnings);
+        }
+
+        let points_reader = LimitedReader::new(reader, points_start..file_size)?;
         let mut points_archive = zip::ZipArchive::new(points_reader)?;
+        let points_entry_count = points_archive.len();
 
-        let cup_file = points_archive.by_name("POINTS.CUP")?;
-        let (cup_file, cup_warnings) = match encoding {
-            Some(encoding) => CupFile::from_reader_with_encoding(cup_file, encoding)?,
-            None => CupFile::from_reader(cup_file)?,
-        };
-        warnings.extend(
-            cup_warnings
-                .into_iter()
-                .map(|issue| Warning::CupParseIssue {
-                    message: issue.message().to_string(),
-                    line: issue.line(),
-                }),
-        );
+        // Some tools write a single archive that contains both `POINTS.CUP` and the
+        // `pics/` directory, which is used as the pics archive as well in that case.
+        let single_zip =
+            pics_boundary.is_none() && points_archive.file_names().any(is_picture_path);
+        if pics_boundary.is_none() && !single_zip {
+            warnings.push(Warning::NoPicturesArchive);
+        }
+
+        let (cup_file, cup_data, cup_header) =
+            read_points_cup(&mut points_archive, options, &mut warnings)?;
 
         // Create pics archive if present
-        let pics_archive = if let Some(boundary) = pics_boundary {
-            let limited_reader = points_archive.into_inner();
-            let reader = limited_reader.into_inner();
-            let pics_reader = LimitedReader::new(reader, 0..boundary)?;
-            Some(zip::ZipArchive::new(pics_reader)?)
+        let mut picture_infos = HashMap::new();
+        let mut root_pictures = false;
+        let (pics_archive, reader) = if single_zip {
+            picture_infos = index_pictures(&mut points_archive, false, &mut warnings);
+
+            if options.eager_validate {
+                validate_pictures(
+                    &mut points_archive,
+                    false,
+                    options.max_picture_size,
+                    &mut warnings,
+                )?;
+            }
+
+            (Some(points_archive), None)
         } else {
-            None
+            let mut reader = points_archive.into_inner().into_inner();
+            match pics_boundary {
+                // With a corrupt comment length the `zip` crate might refuse to open the
+                // pics archive, so in that case we keep going without pictures.
+                Some(boundary)
+                    if suspicious_comment
+                        && zip::ZipArchive::new(LimitedReader::new(&mut reader, 0..boundary)?)
+                            .is_err() =>
+                {
+                    (None, Some(reader))
+                }
+                Some(boundary) => {
+                    let pics_reader = LimitedReader::new(reader, 0..boundary)?;
+                    let mut pics_archive = zip::ZipArchive::new(pics_reader)?;
+                    (picture_infos, root_pictures) =
+                        inspect_pics_archive(&mut pics_archive, options, &mut warnings)?;
+
+                    (Some(pics_archive), None)
+                }
+                None => (None, Some(reader)),
+            }
         };
 
         let cupx_file = Self {
             cup_file,
+            cup_data,
+            cup_header,
+            deferred_warnings: OnceLock::new(),
+            deferred_error: OnceLock::new(),
             pics_archive,
+            reader,
+            pics_boundary,
+            single_zip,
+            root_pictures,
+            picture_infos,
+            max_picture_size: options.max_picture_size,
+            case_sensitive_pictures: false,
+            encoding: options.encoding,
+            file_size,
+            points_entry_count,
+            detached: false,
         };
 
+        let warnings = finish_warnings(warnings, options)?;
         Ok((cupx_file, warnings))
     }
 
-    /// Returns a reference to the parsed CUP file data.
+    /// Builds the file from a `POINTS.CUP` entry salvaged from a damaged points
+    /// archive, see [`ParseOptions::recover`].
     ///
-    /// The [`CupFile`] contains all waypoints and tasks from the CUPX file.
-    pub fn cup_file(&self) -> &CupFile {
-        &self.cup_file
-    }
+    /// If no pics boundary was found, the whole file is tried as the pics
+    /// archive, since the damaged points archive might be the only one.
+    fn from_recovered(
+        mut reader: R,
+        pics_boundary: Option<u64>,
+        file_size: u64,
+        options: &ParseOptions,
+        mut warnings: WarningList,
+    ) -> Result<(Self, Vec<Warning>), Error> {
+        let points_start = pics_boundary.unwrap_or(0);
+        let data = recover_points_cup(&mut reader, points_start..file_size)?;
+        warnings.push(Warning::RecoveredFromCorruptArchive);
 
-    /// Returns a slice of all waypoints in the file.
-    pub fn waypoints(&self) -> &[Waypoint] {
-        &self.cup_file().waypoints
-    }
+        let (cup_file, cup_data, cup_header) = read_cup(data.as_slice(), options, &mut warnings)?;
 
-    /// Returns a slice of all tasks in the file.
-    pub fn tasks(&self) -> &[Task] {
-        &self.cup_file().tasks
+        let pics_end = pics_boundary.unwrap_or(file_size);
+        let pics_archive = zip::ZipArchive::new(LimitedReader::new(&mut reader, 0..pics_end)?)
+            .is_ok_and(|archive| archive.file_names().any(is_picture_path));
+
+        let mut picture_infos = HashMap::new();
+        let mut root_pictures = false;
+        let (pics_archive, reader) = match pics_archive {
+            true => {
+                let mut pics_archive =
+                    zip::ZipArchive::new(LimitedReader::new(reader, 0..pics_end)?)?;
+                (picture_infos, root_pictures) =
+                    inspect_pics_archive(&mut pics_archive, options, &mut warnings)?;
+                (Some(pics_archive), None)
+            }
+            false => {
+                if pics_boundary.is_none() {
+                    warnings.push(Warning::NoPicturesArchive);
+                }
+                (None, Some(reader))
+            }
+        };
+
+        let cupx_file = Self {
+            cup_file,
+            cup_data,
+            cup_header,
+            deferred_warnings: OnceLock::new(),
+            deferred_error: OnceLock::new(),
+            single_zip: pics_archive.is_some() && pics_boundary.is_none(),
+            root_pictures,
+            pics_archive,
+            reader,
+            pics_boundary,
+            picture_infos,
+            max_picture_size: options.max_picture_size,
+            case_sensitive_pictures: false,
+            encoding: options.encoding,
+            file_size,
+            points_entry_count: 0,
+            detached: false,
+        };
+
+        let warnings = finish_warnings(warnings, options)?;
+        Ok((cupx_file, warnings))
+    }
+
+    /// Creates a CUPX file from an already opened points archive and pics archive.
+    ///
+    /// This skips the search for the archive boundary, which is useful if the
+    /// two archives were already located and opened for other purposes. The
+    /// CUP data is parsed from the `POINTS.CUP` file of `points` with automatic
+    /// encoding detection, and `pics` is used for all picture access. Use
+    /// [`from_archives_with_options`](Self::from_archives_with_options) to
+    /// customize parsing.
+    ///
+    /// The two archives may use different reader types. The points reader is
+    /// dropped once the CUP data has been read, so only the reader of `pics` is
+    /// kept. Without a pics archive, the type `R` can't be inferred and has to
+    /// be given explicitly, e.g. `None::<zip::ZipArchive<File>>`.
+    ///
+    /// Since the underlying readers don't cover the complete CUPX data,
+    /// [`raw_bytes`](Self::raw_bytes), [`split`](Self::split) and
+    /// [`prune_unreferenced`](Self::prune_unreferenced) return an error for files
+    /// created this way. [`into_inner`](Self::into_inner) returns the reader of
+    /// `pics`, or `None` if there is no pics archive.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    /// use std::io::Cursor;
+    ///
+    /// let points = zip::ZipArchive::new(Cursor::new(std::fs::read("points.zip")?))?;
+    /// let pics = zip::ZipArchive::new(File::open("pics.zip")?)?;
+    /// let (cupx, warnings) = CupxFile::from_archives(points, Some(pics))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `points` doesn't contain a `POINTS.CUP` file, if the
+    /// CUP data is invalid, or if the pics archive cannot be re-opened.
+    pub fn from_archives<P: Read + Seek>(
+        points: zip::ZipArchive<P>,
+        pics: Option<zip::ZipArchive<R>>,
+    ) -> Result<(Self, Vec<Warning>), Error> {
+        Self::from_archives_with_options(points, pics, ParseOptions::default())
+    }
+
+    /// Creates a CUPX file from an already opened points archive and pics
+    /// archive using the given parse options.
+    ///
+    /// See [`from_archives`](Self::from_archives) for details. Since the
+    /// archives are already open, [`ParseOptions::recover`] and
+    /// [`ParseOptions::search_chunk_size`] have no effect.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::{CupxFile, ParseOptions};
+    /// use std::fs::File;
+    ///
+    /// let points = zip::ZipArchive::new(File::open("points.zip")?)?;
+    /// let pics = zip::ZipArchive::new(File::open("pics.zip")?)?;
+    /// let options = ParseOptions::new().strict(true);
+    /// let (cupx, warnings) = CupxFile::from_archives_with_options(points, Some(pics), options)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in the same cases as [`from_archives`](Self::from_archives),
+    /// and if [`ParseOptions::strict`] is set and warnings were found.
+    pub fn from_archives_with_options<P: Read + Seek>(
+        mut points: zip::ZipArchive<P>,
+        pics: Option<zip::ZipArchive<R>>,
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<Warning>), Error> {
+        let mut warnings = WarningList::new(options.warning_limit());
+
+        if pics.is_none() {
+            warnings.push(Warning::NoPicturesArchive);
+        }
+
+        let (cup_file, cup_data, cup_header) =
+            read_points_cup(&mut points, &options, &mut warnings)?;
+        let points_entry_count = points.len();
+
+        let points_size = points.into_inner().seek(SeekFrom::End(0))?;
+
+        // The pics archive has to be re-opened on top of a `LimitedReader`, which
+        // re-reads its central directory, but not the rest of the data
+        let mut picture_infos = HashMap::new();
+        let mut root_pictures = false;
+        let (pics_archive, pics_size) = match pics {
+            Some(pics) => {
+                let mut pics_reader = pics.into_inner();
+                let pics_size = pics_reader.seek(SeekFrom::End(0))?;
+                let mut pics_archive =
+                    zip::ZipArchive::new(LimitedReader::new(pics_reader, 0..pics_size)?)?;
+                (picture_infos, root_pictures) =
+                    inspect_pics_archive(&mut pics_archive, &options, &mut warnings)?;
+                (Some(pics_archive), Some(pics_size))
+            }
+            None => (None, None),
+        };
+
+        let cupx_file = Self {
+            cup_file,
+            cup_data,
+            cup_header,
+            deferred_warnings: OnceLock::new(),
+            deferred_error: OnceLock::new(),
+            pics_archive,
+            reader: None,
+            pics_boundary: pics_size,
+            single_zip: false,
+            root_pictures,
+            picture_infos,
+            max_picture_size: options.max_picture_size,
+            case_sensitive_pictures: false,
+            encoding: options.encoding,
+            file_size: pics_size.unwrap_or(0) + points_size,
+            points_entry_count,
+            detached: true,
+        };
+
+        let warnings = finish_warnings(warnings, &options)?;
+        Ok((cupx_file, warnings))
+    }
+
+    /// Runs `f` with mutable access to the underlying reader.
+    ///
+    /// If the reader is owned by the pics archive, the archive is taken apart and
+    /// re-opened afterwards, so this should not be used on hot paths.
+    pub(crate) fn with_reader<T>(
+        &mut self,
+        f: impl FnOnce(&mut R) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if self.detached {
+            return Err(Error::Io(std::io::Error::other(
+                "the complete CUPX data is not available for files created from separate archives",
+            )));
+        }
+
+        if let Some(reader) = self.reader.as_mut() {
+            return f(reader);
+        }
+
+        let pics_end = match self.single_zip {
+            true => Some(self.file_size),
+            false => self.pics_boundary,
+        };
+        // Check everything before taking the archive apart, so that it is never lost
+        let Some(pics_end) = pics_end else {
+            return Err(Error::Io(std::io::Error::other(
+                "the end of the pics archive is unknown",
+            )));
+        };
+        let Some(pics_archive) = self.pics_archive.take() else {
+            return Err(Error::Io(std::io::Error::other(
+                "underlying reader is no longer available",
+            )));
+        };
+
+        let mut reader = pics_archive.into_inner().into_inner();
+        let result = f(&mut reader);
+
+        // Make sure the archive can be re-opened before handing the reader over to
+        // it, so that the reader is kept if that fails and `into_inner()` still works
+        let reopened = LimitedReader::new(&mut reader, 0..pics_end)
+            .map_err(Error::from)
+            .and_then(|pics_reader| Ok(zip::ZipArchive::new(pics_reader).map(|_| ())?));
+        if let Err(error) = reopened {
+            self.reader = Some(reader);
+            return Err(error);
+        }
+
+        // This only fails if the reader returns different data than for the check
+        // above, in which case the reader is gone and `into_inner()` returns `None`
+        let pics_reader = LimitedReader::new(reader, 0..pics_end)?;
+        self.pics_archive = Some(zip::ZipArchive::new(pics_reader)?);
+
+        result
+    }
+
+    /// Copies the points archive unchanged from the underlying reader to `out`.
+    ///
+    /// In the single-ZIP layout, the CUP files are part of the pics archive
+    /// and nothing is copied, so they must be copied along with the pictures.
+    pub(crate) fn copy_points_archive(&mut self, out: &mut impl Write) -> Result<(), Error> {
+        if self.single_zip {
+            return Ok(());
+        }
+
+        let points_start = self.pics_boundary.unwrap_or(0);
+        let file_size = self.file_size;
+        self.with_reader(|reader| {
+            let mut points_reader = LimitedReader::new(reader, points_start..file_size)?;
+            std::io::copy(&mut points_reader, out)?;
+            Ok(())
+        })
+    }
+
+    /// Runs `f` with the points archive opened from the underlying reader.
+    ///
+    /// In the single-ZIP layout, this is the complete archive including the
+    /// pictures. See [`with_reader`](Self::with_reader) for the costs.
+    pub(crate) fn with_points_archive<T>(
+        &mut self,
+        f: impl FnOnce(&mut zip::ZipArchive<LimitedReader<&mut R, Range<u64>>>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let points_start = self.pics_boundary.unwrap_or(0);
+        let file_size = self.file_size;
+        self.with_reader(|reader| {
+            let points_reader = LimitedReader::new(reader, points_start..file_size)?;
+            f(&mut zip::ZipArchive::new(points_reader)?)
+        })
+    }
+
+    /// Consumes the `CupxFile` and returns the underlying reader.
+    ///
+    /// The position of the returned reader is unspecified. Returns `None` if
+    /// the reader was lost because re-opening the archive after an operation
+    /// like [`raw_bytes`](Self::raw_bytes) failed, or for files created with
+    /// [`from_archives`](Self::from_archives) without a pics archive.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// println!("Loaded {} waypoints", cupx.waypoints().len());
+    /// let file = cupx.into_inner().expect("reader is available");
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn into_inner(self) -> Option<R> {
+        match (self.reader, self.pics_archive) {
+            (Some(reader), _) => Some(reader),
+            (None, Some(pics_archive)) => Some(pics_archive.into_inner().into_inner()),
+            (None, None) => None,
+        }
+    }
+
+    /// Copies the raw bytes of the two ZIP archives to separate writers.
+    ///
+    /// The pics archive (everything before the boundary) is copied to `pics_out`
+    /// and the points archive (everything after it) to `points_out`. Both outputs
+    /// are standalone ZIP files that can be opened with generic ZIP tools.
+    ///
+    /// Returns `false` if the CUPX file has no separate pictures archive, in which
+    /// case nothing is written to `pics_out`. This includes files using the
+    /// single-ZIP layout, which are copied to `points_out` as a whole.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let has_pics = cupx.split(File::create("pics.zip")?, File::create("points.zip")?)?;
+    /// if !has_pics {
+    ///     std::fs::remove_file("pics.zip")?;
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the underlying reader or writing to
+    /// one of the outputs fails.
+    pub fn split(
+        &mut self,
+        mut pics_out: impl Write,
+        mut points_out: impl Write,
+    ) -> Result<bool, Error> {
+        let pics_boundary = self.pics_boundary;
+        let file_size = self.file_size;
+
+        self.with_reader(|reader| {
+            let points_start = pics_boundary.unwrap_or(0);
+            if points_start > 0 {
+                let mut pics_reader = LimitedReader::new(&mut *reader, 0..points_start)?;
+                std::io::copy(&mut pics_reader, &mut pics_out)?;
+            }
+
+            let mut points_reader = LimitedReader::new(&mut *reader, points_start..file_size)?;
+            std::io::copy(&mut points_reader, &mut points_out)?;
+
+            Ok(pics_boundary.is_some())
+        })
+    }
+
+    /// Returns the raw bytes of the whole CUPX file.
+    ///
+    /// This reads the underlying reader from the start, which is useful for
+    /// forwarding or caching the unmodified file after parsing it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let bytes = cupx.raw_bytes()?;
+    /// println!("{} bytes", bytes.len());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the underlying reader fails.
+    pub fn raw_bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let file_size = self.file_size;
+
+        self.with_reader(|reader| {
+            let mut buffer = Vec::with_capacity(file_size as usize);
+            LimitedReader::new(&mut *reader, 0..file_size)?.read_to_end(&mut buffer)?;
+            Ok(buffer)
+        })
+    }
+
+    /// Returns a reference to the parsed CUP file data.
+    ///
+    /// The [`CupFile`] contains all waypoints and tasks from the CUPX file.
+    pub fn cup_file(&self) -> &CupFile {
+        self.cup_file.get_or_init(|| {
+            let Some((data, options)) = &self.cup_data else {
+                return CupFile::default();
+            };
+
+            // A lower `max_warnings` doesn't apply here, but memory is still bounded
+            let limit = options.warning_limit().max(DEFAULT_MAX_WARNINGS);
+            let mut warnings = WarningList::new(limit);
+            let cup_file =
+                parse_cup(data.as_slice(), options, &mut warnings).unwrap_or_else(|error| {
+                    let message = error.to_string();
+                    warnings = WarningList::new(limit);
+                    warnings.push(Warning::CupParseIssue {
+                        message: message.clone(),
+                        line: None,
+                    });
+                    let _ = self.deferred_error.set(message);
+                    CupFile::default()
+                });
+
+            let _ = self.deferred_warnings.set(warnings.finish());
+            cup_file
+        })
+    }
+
+    /// Returns the parsed CUP data, or an error if deferred parsing failed.
+    ///
+    /// Methods that write the CUP data back use this instead of
+    /// [`cup_file`](Self::cup_file), so that a failed parse doesn't replace
+    /// `POINTS.CUP` with an empty file.
+    pub(crate) fn checked_cup_file(&self) -> Result<&CupFile, Error> {
+        let cup_file = self.cup_file();
+        match self.deferred_error.get() {
+            Some(message) => Err(Error::DeferredCupParse(message.clone())),
+            None => Ok(cup_file),
+        }
+    }
+
+    /// Returns the warnings that occurred while parsing the CUP data on first access.
+    ///
+    /// This is only relevant for files opened with
+    /// [`from_reader_pics_only`](Self::from_reader_pics_only) or with
+    /// [`ParseOptions::lazy_cup`], where parsing the CUP data is deferred until
+    /// [`cup_file`](Self::cup_file), [`waypoints`](Self::waypoints) or
+    /// [`tasks`](Self::tasks) is first called. Calling this method triggers
+    /// parsing as well. If the CUP data is invalid,
+    /// the error is reported as a [`Warning::CupParseIssue`] and the file appears
+    /// to contain no waypoints or tasks.
+    ///
+    /// For files whose CUP data was parsed immediately, the warnings were already
+    /// returned by the constructor, and this returns an empty slice.
+    pub fn deferred_warnings(&self) -> &[Warning] {
+        self.cup_file();
+        self.deferred_warnings.get().map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the header line of `POINTS.CUP`.
+    ///
+    /// The header names the columns of the waypoint table, so it can be used to
+    /// tell which CUP dialect a file uses, e.g. whether it contains the newer
+    /// `userdata` and `pics` columns that older devices don't support. The
+    /// line is returned without the line ending and a leading byte order mark.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// if cupx.cup_header().to_ascii_lowercase().contains("pics") {
+    ///     println!("File uses the extended column set");
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn cup_header(&self) -> &str {
+        &self.cup_header
+    }
+
+    /// Returns a slice of all waypoints in the file.
+    pub fn waypoints(&self) -> &[Waypoint] {
+        &self.cup_file().waypoints
+    }
+
+    /// Returns a slice of all tasks in the file.
+    pub fn tasks(&self) -> &[Task] {
+        &self.cup_file().tasks
+    }
+
+    /// Returns a mutable reference to the parsed CUP file data.
+    ///
+    /// This allows editing the waypoints and tasks in place, e.g. before passing
+    /// them to a [`CupxWriter`](crate::CupxWriter). If parsing is deferred, the
+    /// CUP data is parsed first.
+    ///
+    /// Changes only affect the parsed data. Methods that copy the points archive
+    /// as-is, like [`split`](Self::split), [`raw_bytes`](Self::raw_bytes) or
+    /// [`prune_unreferenced`](Self::prune_unreferenced), still write the original
+    /// `POINTS.CUP`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::{CupxFile, CupxWriter};
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// cupx.cup_file_mut().tasks.clear();
+    /// CupxWriter::new(cupx.cup_file()).write_to_path("without-tasks.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn cup_file_mut(&mut self) -> &mut CupFile {
+        self.cup_file();
+        self.cup_file
+            .get_mut()
+            .expect("the CUP data was parsed by `cup_file()`")
+    }
+
+    /// Returns a mutable reference to the waypoints in the file.
+    ///
+    /// See [`cup_file_mut`](Self::cup_file_mut) for details.
+    pub fn waypoints_mut(&mut self) -> &mut Vec<Waypoint> {
+        &mut self.cup_file_mut().waypoints
+    }
+
+    /// Returns a mutable reference to the tasks in the file.
+    ///
+    /// See [`cup_file_mut`](Self::cup_file_mut) for details.
+    pub fn tasks_mut(&mut self) -> &mut Vec<Task> {
+        &mut self.cup_file_mut().tasks
+    }
+
+    /// Returns an iterator over all waypoints matching `predicate`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let with_pictures = cupx.waypoints_matching(|waypoint| !waypoint.pictures.is_empty());
+    /// println!("{} waypoints have pictures", with_pictures.count());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn waypoints_matching(
+        &self,
+        predicate: impl Fn(&Waypoint) -> bool,
+    ) -> impl Iterator<Item = &Waypoint> {
+        self.waypoints()
+            .iter()
+            .filter(move |waypoint| predicate(waypoint))
+    }
+
+    /// Returns an iterator over all waypoints with the given style.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use seeyou_cupx::cup::WaypointStyle;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for airport in cupx.waypoints_with_style(WaypointStyle::SolidAirfield) {
+    ///     println!("{}", airport.name);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn waypoints_with_style(&self, style: WaypointStyle) -> impl Iterator<Item = &Waypoint> {
+        self.waypoints_matching(move |waypoint| waypoint.style == style)
+    }
+
+    /// Returns the number of waypoints per style.
+    ///
+    /// Only styles that occur in the file are included, ordered by their numeric
+    /// value in the CUP format. A `Vec` is returned instead of a map because
+    /// [`WaypointStyle`] implements neither `Hash` nor `Ord`.
+    pub fn count_by_style(&self) -> Vec<(WaypointStyle, usize)> {
+        let mut counts: Vec<(WaypointStyle, usize)> = Vec::new();
+        for waypoint in self.waypoints() {
+            match counts
+                .iter_mut()
+                .find(|(style, _)| *style == waypoint.style)
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((waypoint.style, 1)),
+            }
+        }
+        counts.sort_by_key(|(style, _)| *style as u8);
+        counts
+    }
+
+    /// Returns all waypoints grouped by their country code.
+    ///
+    /// Waypoints without a country code are grouped under the empty string.
+    /// Within each group, the waypoints keep their order from the file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for (country, waypoints) in cupx.waypoints_by_country() {
+    ///     println!("{}: {} waypoints", country, waypoints.len());
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn waypoints_by_country(&self) -> BTreeMap<String, Vec<&Waypoint>> {
+        let mut result: BTreeMap<String, Vec<&Waypoint>> = BTreeMap::new();
+        for waypoint in self.waypoints() {
+            result
+                .entry(waypoint.country.clone())
+                .or_default()
+                .push(waypoint);
+        }
+        result
+    }
+
+    /// Returns the distinct country codes of all waypoints.
+    ///
+    /// Contains the empty string if any waypoint has no country code.
+    pub fn countries(&self) -> BTreeSet<String> {
+        self.waypoints()
+            .iter()
+            .map(|waypoint| waypoint.country.clone())
+            .collect()
+    }
+
+    /// Returns the geographic extent of all waypoints.
+    ///
+    /// Waypoints with out-of-range coordinates (see
+    /// [`Warning::InvalidCoordinates`]) are ignored. Returns `None` if there are
+    /// no other waypoints. The longitude range is not wrapped around the
+    /// antimeridian, see [`BoundingBox`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// if let Some(bbox) = cupx.bounding_box() {
+    ///     println!("{}..{} N, {}..{} E", bbox.min_lat, bbox.max_lat, bbox.min_lon, bbox.max_lon);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.waypoints()
+            .iter()
+            .filter(|waypoint| {
+                (-90.0..=90.0).contains(&waypoint.latitude)
+                    && (-180.0..=180.0).contains(&waypoint.longitude)
+            })
+            .fold(None, |bbox: Option<BoundingBox>, waypoint| {
+                let (lat, lon) = (waypoint.latitude, waypoint.longitude);
+                Some(match bbox {
+                    None => BoundingBox {
+                        min_lat: lat,
+                        max_lat: lat,
+                        min_lon: lon,
+                        max_lon: lon,
+                    },
+                    Some(bbox) => BoundingBox {
+                        min_lat: bbox.min_lat.min(lat),
+                        max_lat: bbox.max_lat.max(lat),
+                        min_lon: bbox.min_lon.min(lon),
+                        max_lon: bbox.max_lon.max(lon),
+                    },
+                })
+            })
+    }
+
+    /// Returns the name and number of points of every task.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for task in cupx.task_summaries() {
+    ///     println!("{} ({} points)", task.name, task.point_count);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn task_summaries(&self) -> Vec<TaskSummary> {
+        self.tasks()
+            .iter()
+            .map(|task| TaskSummary {
+                name: task.description.clone().unwrap_or_default(),
+                point_count: task.waypoint_names.len(),
+                distance: self.task_distance(task),
+            })
+            .collect()
+    }
+
+    /// Returns the total length of a task in meters.
+    ///
+    /// Each name in [`Task::waypoint_names`] is resolved to the first waypoint
+    /// with that name, ignoring case, and the great-circle distances of all legs
+    /// between consecutive points are summed up. Since all points of the task
+    /// are included, legs to and from the takeoff and landing points count as
+    /// well if the task lists them.
+    ///
+    /// Returns `None` if any of the points can't be resolved.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for task in cupx.tasks() {
+    ///     if let Some(distance) = cupx.task_distance(task) {
+    ///         println!("{:.1} km", distance / 1000.0);
+    ///     }
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn task_distance(&self, task: &Task) -> Option<f64> {
+        let points = task
+            .waypoint_names
+            .iter()
+            .map(|name| {
+                let name = name.to_lowercase();
+                self.waypoints()
+                    .iter()
+                    .find(|waypoint| waypoint.name.to_lowercase() == name)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(
+            points
+                .windows(2)
+                .map(|leg| geo::distance(leg[0], leg[1]))
+                .sum(),
+        )
+    }
+
+    /// Returns the observation zones of a task together with the point they
+    /// belong to.
+    ///
+    /// The zones come from the `ObsZone` lines of the task, see
+    /// [`Task::observation_zones`]. Zone numbers start at the start point, which
+    /// is the second entry of [`Task::waypoint_names`] after the takeoff. Each
+    /// point is taken from the inline `Point` definitions of the task if there
+    /// is one, and otherwise resolved to the first waypoint with that name,
+    /// ignoring case.
+    ///
+    /// The zones are returned in the order of their numbers. Zones whose point
+    /// can't be resolved are skipped. Points without an `ObsZone` line have no
+    /// entry, and use the default zone of the task consumer.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for task in cupx.tasks() {
+    ///     for (waypoint, zone) in cupx.task_zones(task) {
+    ///         println!("{}: {:?} {:?}", waypoint.name, zone.style, zone.r1);
+    ///     }
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn task_zones<'a>(&'a self, task: &'a Task) -> Vec<(&'a Waypoint, &'a ObservationZone)> {
+        let mut zones = task
+            .observation_zones
+            .iter()
+            .filter_map(|zone| {
+                let position = zone.index as usize + 1;
+                let inline = task
+                    .points
+                    .iter()
+                    .find(|(index, _)| *index as usize == position)
+                    .map(|(_, waypoint)| waypoint);
+                let waypoint = inline.or_else(|| {
+                    let name = task.waypoint_names.get(position)?.to_lowercase();
+                    self.waypoints()
+                        .iter()
+                        .find(|waypoint| waypoint.name.to_lowercase() == name)
+                })?;
+                Some((waypoint, zone))
+            })
+            .collect::<Vec<_>>();
+
+        zones.sort_by_key(|(_, zone)| zone.index);
+        zones
+    }
+
+    /// Returns a reader for the picture with the given filename.
+    ///
+    /// The filename should not include the `pics/` prefix. Matching is case-insensitive,
+    /// see [`set_case_sensitive_pictures`](Self::set_case_sensitive_pictures).
+    ///
+    /// Only one picture can be read at a time, as this method requires `&mut self`.
+    ///
+    /// The returned [`PictureReader`] also reports the uncompressed size of the
+    /// picture and the number of bytes read so far.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::io::Read;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let mut reader = cupx.read_picture("airport.jpg")?;
+    ///
+    /// let mut buffer = Vec::new();
+    /// reader.read_to_end(&mut buffer)?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the picture doesn't exist or if the CUPX file
+    /// doesn't contain a pictures archive.
+    pub fn read_picture(&mut self, filename: &str) -> Result<PictureReader<'_, R>, Error> {
+        let actual_path = self
+            .find_picture_path(filename)
+            .ok_or(zip::result::ZipError::FileNotFound)?;
+
+        let pics_archive = self
+            .pics_archive
+            .as_mut()
+            .ok_or(zip::result::ZipError::FileNotFound)?;
+
+        let file = pics_archive.by_name(&actual_path)?;
+        check_picture_size(&file, self.max_picture_size)?;
+        Ok(PictureReader::new(file))
+    }
+
+    /// Reads the picture with the given filename and detects its image format.
+    ///
+    /// The format is detected from the first bytes of the content, like in
+    /// [`PictureFormat::detect`], so the picture is only decompressed once. It is
+    /// `None` if the content doesn't match any of the known formats, regardless
+    /// of the file extension.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::{CupxFile, PictureFormat};
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let (format, data) = cupx.read_picture_typed("airport.jpg")?;
+    /// if format == Some(PictureFormat::Png) {
+    ///     println!("{} bytes of PNG data", data.len());
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the picture doesn't exist, if the CUPX file doesn't
+    /// contain a pictures archive, or if the picture exceeds
+    /// [`ParseOptions::max_picture_size`].
+    pub fn read_picture_typed(
+        &mut self,
+        filename: &str,
+    ) -> Result<(Option<PictureFormat>, Vec<u8>), Error> {
+        let reader = self.read_picture(filename)?;
+        let size = reader.size();
+        let data = read_sized(reader, size)?;
+
+        Ok((PictureFormat::detect(&data), data))
+    }
+
+    /// Returns a reader for the picture with the given full archive path.
+    ///
+    /// Unlike [`read_picture`](Self::read_picture), the path must include the
+    /// `pics/` prefix and any subdirectories, e.g. `pics/Alps/summit.jpg`.
+    /// Matching is case-insensitive unless
+    /// [`set_case_sensitive_pictures`](Self::set_case_sensitive_pictures) was
+    /// enabled. Entries outside of the `pics/` directory
+    /// can't be read with this method.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::io::Read;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let mut buffer = Vec::new();
+    /// cupx.read_picture_by_path("pics/Alps/summit.jpg")?
+    ///     .read_to_end(&mut buffer)?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no picture with the given path exists or if it
+    /// exceeds [`ParseOptions::max_picture_size`].
+    pub fn read_picture_by_path(&mut self, full_path: &str) -> Result<PictureReader<'_, R>, Error> {
+        let pics_archive = self
+            .pics_archive
+            .as_mut()
+            .ok_or(zip::result::ZipError::FileNotFound)?;
+
+        let target_path = full_path.to_lowercase();
+        let actual_path = pics_archive
+            .file_names()
+            .find(|name| {
+                if self.case_sensitive_pictures {
+                    // Only the `pics/` prefix is matched case-insensitively
+                    picture_name(name, self.root_pictures).is_some_and(|filename| {
+                        let prefix_len = name.len() - filename.len();
+                        full_path.len() == name.len()
+                            && full_path.is_char_boundary(prefix_len)
+                            && full_path[..prefix_len].eq_ignore_ascii_case(&name[..prefix_len])
+                            && full_path[prefix_len..] == *filename
+                    })
+                } else {
+                    picture_name(name, self.root_pictures).is_some()
+                        && name.to_lowercase() == target_path
+                }
+            })
+            .map(|name| name.to_string())
+            .ok_or(zip::result::ZipError::FileNotFound)?;
+
+        let file = pics_archive.by_name(&actual_path)?;
+        check_picture_size(&file, self.max_picture_size)?;
+        Ok(PictureReader::new(file))
+    }
+
+    /// Returns the full archive path of the picture with the given filename.
+    ///
+    /// The filename should not include the `pics/` prefix. Matching is
+    /// case-insensitive unless [`set_case_sensitive_pictures`](Self::set_case_sensitive_pictures)
+    /// was enabled.
+    pub(crate) fn find_picture_path(&self, filename: &str) -> Option<String> {
+        let pics_archive = self.pics_archive.as_ref()?;
+
+        // Try to find the file with case-insensitive prefix matching
+        let target_filename = filename.to_lowercase();
+        pics_archive
+            .file_names()
+            .find(|name| {
+                picture_name(name, self.root_pictures).is_some_and(|name| {
+                    if self.case_sensitive_pictures {
+                        name == filename
+                    } else {
+                        name.to_lowercase() == target_filename
+                    }
+                })
+            })
+            .map(|name| name.to_string())
+    }
+
+    /// Sets whether picture filenames are matched case-sensitively.
+    ///
+    /// By default, [`read_picture`](Self::read_picture),
+    /// [`read_picture_by_path`](Self::read_picture_by_path) and
+    /// [`picture`](Self::picture) ignore case, so `Airport.JPG` finds
+    /// `pics/airport.jpg`. When enabled, the filename has to match exactly,
+    /// which allows archives containing both `Foo.jpg` and `foo.jpg` to be read
+    /// unambiguously. The `pics/` prefix is still matched case-insensitively.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// cupx.set_case_sensitive_pictures(true);
+    /// assert!(cupx.picture("AIRPORT.JPG").is_none());
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn set_case_sensitive_pictures(&mut self, enabled: bool) {
+        self.case_sensitive_pictures = enabled;
+    }
+
+    /// Returns metadata about the picture with the given filename.
+    ///
+    /// The filename should not include the `pics/` prefix. Matching is case-insensitive,
+    /// see [`set_case_sensitive_pictures`](Self::set_case_sensitive_pictures).
+    ///
+    /// Unlike [`read_picture`](Self::read_picture), this does not open the picture
+    /// and returns `None` instead of an error if the picture doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// if let Some(info) = cupx.picture("airport.jpg") {
+    ///     println!("{} bytes ({} compressed)", info.size, info.compressed_size);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn picture(&self, filename: &str) -> Option<PictureInfo> {
+        let path = self.find_picture_path(filename)?;
+        self.picture_infos.get(&path).copied()
+    }
+
+    /// Returns the compression method of the picture with the given filename.
+    ///
+    /// This is a shortcut for the [`compression`](PictureInfo::compression) of
+    /// [`picture`](Self::picture), read from the central directory without
+    /// decompressing anything. Returns `None` if the picture doesn't exist.
+    pub fn picture_compression(&self, filename: &str) -> Option<zip::CompressionMethod> {
+        self.picture(filename).map(|info| info.compression)
+    }
+
+    /// Returns an iterator over the filenames and compression methods of all
+    /// pictures.
+    ///
+    /// The filenames are returned without the `pics/` prefix, in archive order.
+    /// Combined with [`picture`](Self::picture) this can be used to find out
+    /// which pictures would benefit from recompressing.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use zip::CompressionMethod;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let stored = cupx
+    ///     .picture_compressions()
+    ///     .filter(|(_, method)| *method == CompressionMethod::Stored)
+    ///     .count();
+    /// println!("{stored} pictures are stored uncompressed");
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn picture_compressions(
+        &self,
+    ) -> impl Iterator<Item = (String, zip::CompressionMethod)> + '_ {
+        self.picture_paths().filter_map(|path| {
+            let name = picture_name(path, self.root_pictures)?;
+            let info = self.picture_infos.get(path)?;
+            Some((name.to_string(), info.compression))
+        })
+    }
+
+    /// Returns the total uncompressed size of all pictures in bytes.
+    ///
+    /// This only reads metadata and doesn't decompress anything. Returns `0` if the
+    /// CUPX file doesn't contain a pictures archive.
+    pub fn pictures_total_size(&self) -> u64 {
+        self.picture_infos.values().map(|info| info.size).sum()
+    }
+
+    /// Returns the total size of all pictures inside the archive in bytes.
+    ///
+    /// Together with [`pictures_total_size`](Self::pictures_total_size) this can be
+    /// used to calculate the compression ratio of the pictures. This only reads
+    /// metadata and doesn't decompress anything. Returns `0` if the CUPX file
+    /// doesn't contain a pictures archive.
+    pub fn pictures_compressed_size(&self) -> u64 {
+        self.picture_infos
+            .values()
+            .map(|info| info.compressed_size)
+            .sum()
+    }
+
+    /// Returns the ratio of the compressed to the uncompressed size of the
+    /// picture with the given filename.
+    ///
+    /// Values near `1.0` mean that compressing the picture saved next to
+    /// nothing, which is typical for already compressed formats like JPEG.
+    /// This only reads metadata. Returns `None` if the picture doesn't exist or
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for name in cupx.picture_names() {
+    ///     if let Some(ratio) = cupx.picture_compression_ratio(&name) {
+    ///         println!("{name}: {:.0}%", ratio * 100.0);
+    ///     }
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn picture_compression_ratio(&self, filename: &str) -> Option<f64> {
+        let info = self.picture(filename)?;
+        (info.size > 0).then(|| info.compressed_size as f64 / info.size as f64)
+    }
+
+    /// Returns the ratio of the compressed to the uncompressed size of all
+    /// pictures together.
+    ///
+    /// This is [`pictures_compressed_size`](Self::pictures_compressed_size)
+    /// divided by [`pictures_total_size`](Self::pictures_total_size). Returns
+    /// `1.0` if there are no pictures or they are all empty.
+    pub fn overall_compression_ratio(&self) -> f64 {
+        match self.pictures_total_size() {
+            0 => 1.0,
+            total_size => self.pictures_compressed_size() as f64 / total_size as f64,
+        }
+    }
+
+    /// Returns the entry counts and sizes of the two ZIP archives.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let stats = cupx.archive_stats();
+    /// println!(
+    ///     "pics: {} entries, {} bytes",
+    ///     stats.pics_entry_count, stats.pics_archive_size
+    /// );
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn archive_stats(&self) -> ArchiveStats {
+        let pics_entry_count = self
+            .pics_archive
+            .as_ref()
+            .map_or(0, |archive| archive.len());
+
+        if self.single_zip {
+            return ArchiveStats {
+                pics_entry_count,
+                points_entry_count: pics_entry_count,
+                pics_archive_size: self.file_size,
+                points_archive_size: self.file_size,
+            };
+        }
+
+        let pics_archive_size = self.pics_boundary.unwrap_or(0);
+        ArchiveStats {
+            pics_entry_count,
+            points_entry_count: self.points_entry_count,
+            pics_archive_size,
+            points_archive_size: self.file_size - pics_archive_size,
+        }
+    }
+
+    /// Returns the byte range of the underlying data that the pics archive is
+    /// read from.
+    ///
+    /// This is mostly useful for debugging custom readers, since all reads of
+    /// pictures are restricted to this range. Returns `None` if the file has no
+    /// pictures archive or it couldn't be opened. In the single-ZIP layout, the
+    /// range covers the whole file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// if let Some(range) = cupx.pics_range() {
+    ///     println!("pics: {range:?}, points: {}..", cupx.points_start());
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn pics_range(&self) -> Option<Range<u64>> {
+        self.pics_archive.as_ref()?;
+        match self.single_zip {
+            true => Some(0..self.file_size),
+            false => self.pics_boundary.map(|boundary| 0..boundary),
+        }
     }
 
-    /// Returns a reader for the picture with the given filename.
+    /// Returns the byte offset of the underlying data where the points archive
+    /// starts.
     ///
-    /// The filename should not include the `pics/` prefix. Matching is case-insensitive.
+    /// The points archive extends to the end of the data. This is `0` for files
+    /// without a pictures archive and for the single-ZIP layout. For files
+    /// created with [`from_archives`](Self::from_archives), it is the size of
+    /// the pics archive, as if both archives were concatenated.
+    pub fn points_start(&self) -> u64 {
+        self.pics_boundary.unwrap_or(0)
+    }
+
+    /// Reads the preview image of the CUPX file.
     ///
-    /// Only one picture can be read at a time, as this method requires `&mut self`.
+    /// Returns the decompressed bytes of the picture named
+    /// [`DEFAULT_PREVIEW_NAME`](crate::DEFAULT_PREVIEW_NAME), or `None` if the
+    /// file doesn't contain a preview. Use
+    /// [`preview_with_name`](Self::preview_with_name) for files written with a
+    /// custom preview name.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use seeyou_cupx::CupxFile;
-    /// use std::io::Read;
     ///
     /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
-    /// let mut reader = cupx.read_picture("airport.jpg")?;
+    /// if let Some(preview) = cupx.preview()? {
+    ///     println!("Preview: {} bytes", preview.len());
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
     ///
-    /// let mut buffer = Vec::new();
-    /// reader.read_to_end(&mut buffer)?;
+    /// # Errors
+    ///
+    /// Returns an error if the preview exists but cannot be read.
+    pub fn preview(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        self.preview_with_name(crate::DEFAULT_PREVIEW_NAME)
+    }
+
+    /// Reads the preview image stored under a custom name.
+    ///
+    /// See [`preview`](Self::preview) and
+    /// [`CupxWriter::preview_name`](crate::CupxWriter::preview_name).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the preview exists but cannot be read.
+    pub fn preview_with_name(&mut self, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        if self.find_picture_path(name).is_none() {
+            return Ok(None);
+        }
+
+        let mut buffer = Vec::new();
+        self.read_picture(name)?.read_to_end(&mut buffer)?;
+        Ok(Some(buffer))
+    }
+
+    /// Reads all pictures referenced by each waypoint.
+    ///
+    /// Returns a map from waypoint name to the decompressed bytes of every picture
+    /// referenced in the waypoint's `pictures` list, in the order they are listed.
+    /// Waypoints without pictures map to an empty list. Referenced pictures that
+    /// don't exist in the pictures archive are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    ///
+    /// for (name, pictures) in cupx.all_waypoint_pictures()? {
+    ///     println!("{}: {} pictures", name, pictures.len());
+    /// }
     /// # Ok::<(), seeyou_cupx::Error>(())
     /// ```
     ///
     /// # Errors
     ///
-    /// Returns an error if the picture doesn't exist or if the CUPX file
-    /// doesn't contain a pictures archive.
-    pub fn read_picture(&mut self, filename: &str) -> Result<impl Read + '_, Error> {
-        let pics_archive = self
-            .pics_archive
-            .as_mut()
-            .ok_or(zip::result::ZipError::FileNotFound)?;
+    /// Returns an error if reading one of the existing pictures fails.
+    pub fn all_waypoint_pictures(&mut self) -> Result<HashMap<String, Vec<Vec<u8>>>, Error> {
+        let mut result: HashMap<String, Vec<Vec<u8>>> = HashMap::new();
 
-        // Try to find the file with case-insensitive prefix matching
-        let target_filename = filename.to_lowercase();
-        let actual_path = pics_archive
-            .file_names()
-            .find(|name| {
-                name.len() >= 5
-                    && name.is_char_boundary(5)
-                    && name[..5].eq_ignore_ascii_case("pics/")
-                    && name[5..].to_lowercase() == target_filename
-            })
-            .ok_or(zip::result::ZipError::FileNotFound)?
-            .to_string();
+        for index in 0..self.waypoints().len() {
+            let waypoint = &self.waypoints()[index];
+            let name = waypoint.name.clone();
+            let paths = waypoint
+                .pictures
+                .iter()
+                .filter_map(|picture| self.find_picture_path(picture))
+                .collect::<Vec<_>>();
 
-        let file = pics_archive.by_name(&actual_path)?;
-        Ok(file)
+            let pictures = result.entry(name).or_default();
+            if let Some(pics_archive) = self.pics_archive.as_mut() {
+                for path in paths {
+                    let mut file = pics_archive.by_name(&path)?;
+                    check_picture_size(&file, self.max_picture_size)?;
+
+                    let mut buffer = Vec::new();
+                    file.read_to_end(&mut buffer)?;
+                    pictures.push(buffer);
+                }
+            }
+        }
+
+        Ok(result)
     }
 
     /// Returns an iterator over all picture filenames in the CUPX file.
     ///
-    /// Filenames do not include the `pics/` prefix. If the CUPX file doesn't
-    /// contain a pictures archive, the iterator will be empty.
+    /// Filenames do not include the `pics/` prefix. Directory entries are
+    /// skipped. If the CUPX file doesn't contain a pictures archive, the
+    /// iterator will be empty.
     ///
     /// # Examples
     ///
@@ -294,20 +2058,660 @@ impl<R: Read + Seek> CupxFile<R> {
     /// # Ok::<(), seeyou_cupx::Error>(())
     /// ```
     pub fn picture_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.picture_names_matching(|_| true)
+    }
+
+    /// Returns an iterator over the full archive paths of all pictures.
+    ///
+    /// Unlike [`picture_names`](Self::picture_names), the paths keep their
+    /// `pics/` prefix exactly as stored in the archive, and they are borrowed
+    /// from the archive without allocating. Directory entries are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    ///
+    /// for path in cupx.picture_paths() {
+    ///     println!("Entry: {}", path);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn picture_paths(&self) -> impl Iterator<Item = &str> {
         self.pics_archive
             .as_ref()
             .into_iter()
             .flat_map(|archive| archive.file_names())
-            .filter_map(|name| {
-                // Handle case-insensitive "pics/" prefix
-                if name.len() >= 5
-                    && name.is_char_boundary(5)
-                    && name[..5].eq_ignore_ascii_case("pics/")
-                {
-                    Some(name[5..].to_string())
-                } else {
-                    None
-                }
+            .filter(|name| picture_name(name, self.root_pictures).is_some() && !name.ends_with('/'))
+    }
+
+    /// Returns an iterator over all picture filenames matching `predicate`.
+    ///
+    /// The predicate receives the filename without the `pics/` prefix, and only
+    /// matching names are allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    ///
+    /// for name in cupx.picture_names_matching(|name| name.starts_with("1_")) {
+    ///     println!("Picture: {}", name);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn picture_names_matching(
+        &self,
+        predicate: impl Fn(&str) -> bool,
+    ) -> impl Iterator<Item = String> {
+        self.picture_paths()
+            .filter_map(|path| picture_name(path, self.root_pictures))
+            .filter(move |name| predicate(name))
+            .map(str::to_string)
+    }
+
+    /// Returns an iterator over all picture filenames with the given extension.
+    ///
+    /// The extension is given without the leading dot and is matched
+    /// case-insensitively, so `"jpg"` matches both `a.jpg` and `B.JPG`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let jpegs = cupx.picture_names_with_extension("jpg").count();
+    /// println!("{} JPEG pictures", jpegs);
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn picture_names_with_extension<'a>(
+        &'a self,
+        ext: &'a str,
+    ) -> impl Iterator<Item = String> + 'a {
+        self.picture_names_matching(move |name| {
+            name.rsplit_once('.')
+                .is_some_and(|(_, extension)| extension.eq_ignore_ascii_case(ext))
+        })
+    }
+
+    /// Returns the names of all pictures that are not referenced by any waypoint.
+    ///
+    /// Picture references are matched case-insensitively, like in
+    /// [`read_picture`](Self::read_picture). The names don't include the `pics/`
+    /// prefix and are sorted alphabetically.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for name in cupx.unreferenced_pictures() {
+    ///     println!("Orphan: {}", name);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn unreferenced_pictures(&self) -> Vec<String> {
+        let referenced = self
+            .waypoints()
+            .iter()
+            .flat_map(|waypoint| &waypoint.pictures)
+            .map(|picture| picture.to_lowercase())
+            .collect::<HashSet<_>>();
+
+        let mut names = self
+            .picture_infos
+            .keys()
+            .filter_map(|path| picture_name(path, self.root_pictures))
+            .filter(|name| !referenced.contains(&name.to_lowercase()))
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Returns a report of how the stored pictures and the picture references
+    /// of the waypoints line up.
+    ///
+    /// Picture references are matched case-insensitively, like in
+    /// [`unreferenced_pictures`](Self::unreferenced_pictures), and references
+    /// differing only in case are counted once.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// let coverage = cupx.picture_coverage();
+    /// println!(
+    ///     "{} of {} referenced pictures present",
+    ///     coverage.referenced_and_present, coverage.referenced
+    /// );
+    /// for name in &coverage.referenced_but_missing {
+    ///     println!("Missing: {}", name);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn picture_coverage(&self) -> PictureCoverage {
+        let stored = self
+            .picture_infos
+            .keys()
+            .filter_map(|path| picture_name(path, self.root_pictures))
+            .map(|name| name.to_lowercase())
+            .collect::<HashSet<_>>();
+
+        let mut referenced = HashSet::new();
+        let mut referenced_but_missing = Vec::new();
+        for picture in self
+            .waypoints()
+            .iter()
+            .flat_map(|waypoint| &waypoint.pictures)
+        {
+            let lowercase = picture.to_lowercase();
+            if !stored.contains(&lowercase) && !referenced.contains(&lowercase) {
+                referenced_but_missing.push(picture.clone());
+            }
+            referenced.insert(lowercase);
+        }
+        referenced_but_missing.sort();
+
+        PictureCoverage {
+            stored: self.picture_infos.len(),
+            referenced: referenced.len(),
+            referenced_and_present: referenced.len() - referenced_but_missing.len(),
+            referenced_but_missing,
+            stored_but_unreferenced: self.unreferenced_pictures(),
+        }
+    }
+}
+
+/// Collects the metadata of all `pics/` entries in the pics archive.
+///
+/// Entries whose local header cannot be read are skipped, so that a single broken
+/// picture doesn't prevent the rest of the file from loading.
+fn index_pictures<R: Read + Seek>(
+    pics_archive: &mut zip::ZipArchive<R>,
+    root_pictures: bool,
+    warnings: &mut WarningList,
+) -> HashMap<String, PictureInfo> {
+    let mut picture_infos = HashMap::new();
+    for index in 0..pics_archive.len() {
+        let Ok(file) = pics_archive.by_index_raw(index) else {
+            continue;
+        };
+
+        if let Some(name) = picture_name(file.name(), root_pictures)
+            && !file.is_dir()
+        {
+            // Names without the UTF-8 flag are decoded as CP437 by the `zip` crate,
+            // so the decoded name only matches the raw bytes for UTF-8 names. The
+            // prefix is plain ASCII, so it has the same length in both.
+            let raw = file.name_raw();
+            if !raw.is_ascii() && file.name().as_bytes() != raw {
+                let prefix_len = file.name().len() - name.len();
+                warnings.push(Warning::NonUtf8PictureName {
+                    raw: raw[prefix_len..].to_vec(),
+                });
+            }
+
+            picture_infos.insert(file.name().to_string(), PictureInfo::from_zip_file(&file));
+        }
+    }
+    picture_infos
+}
+
+/// Decompresses all pictures in the pics archive to verify their checksums, and
+/// checks that their content matches their file extension.
+fn validate_pictures<R: Read + Seek>(
+    pics_archive: &mut zip::ZipArchive<R>,
+    root_pictures: bool,
+    max_picture_size: Option<u64>,
+    warnings: &mut WarningList,
+) -> Result<(), Error> {
+    for index in 0..pics_archive.len() {
+        let mut file = pics_archive.by_index(index)?;
+        let Some(name) = picture_name(file.name(), root_pictures).map(str::to_string) else {
+            continue;
+        };
+
+        check_picture_size(&file, max_picture_size)?;
+
+        let mut header = Vec::with_capacity(PictureFormat::HEADER_LEN);
+        (&mut file)
+            .take(PictureFormat::HEADER_LEN as u64)
+            .read_to_end(&mut header)?;
+        if let Some(warning) = check_picture_format(&name, &header) {
+            warnings.push(warning);
+        }
+
+        std::io::copy(&mut file, &mut std::io::sink())?;
+    }
+    Ok(())
+}
+
+/// Returns a warning if the picture's extension doesn't match its content.
+fn check_picture_format(name: &str, header: &[u8]) -> Option<Warning> {
+    let (extension, detected) = format_mismatch(name, header)?;
+    Some(Warning::PictureFormatMismatch {
+        name: name.to_string(),
+        extension: extension.to_string(),
+        detected,
+    })
+}
+
+/// Returns the extension and the detected format of a picture if they don't
+/// match. Pictures with unknown extensions or formats are never reported.
+pub(crate) fn format_mismatch<'a>(
+    name: &'a str,
+    header: &[u8],
+) -> Option<(&'a str, PictureFormat)> {
+    let (_, extension) = name.rsplit_once('.')?;
+    let expected = PictureFormat::from_extension(extension)?;
+    let detected = PictureFormat::detect(header)?;
+    (expected != detected).then_some((extension, detected))
+}
+
+/// Returns an error if the picture is larger than `max_picture_size`.
+pub(crate) fn check_picture_size<R: Read>(
+    file: &zip::read::ZipFile<'_, R>,
+    max_picture_size: Option<u64>,
+) -> Result<(), Error> {
+    match max_picture_size {
+        Some(max_size) if file.size() > max_size => Err(Error::PictureTooLarge {
+            name: file.name().to_string(),
+            size: file.size(),
+            max_size,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Returns `true` if both readers yield the same bytes.
+pub(crate) fn same_content(mut a: impl BufRead, mut b: impl BufRead) -> std::io::Result<bool> {
+    loop {
+        let (chunk_a, chunk_b) = (a.fill_buf()?, b.fill_buf()?);
+        if chunk_a.is_empty() || chunk_b.is_empty() {
+            return Ok(chunk_a.is_empty() && chunk_b.is_empty());
+        }
+
+        let len = chunk_a.len().min(chunk_b.len());
+        if chunk_a[..len] != chunk_b[..len] {
+            return Ok(false);
+        }
+        a.consume(len);
+        b.consume(len);
+    }
+}
+
+/// Upper bound for the capacity reserved up front for the content of a ZIP
+/// entry, since the size in its header can't be trusted.
+const MAX_PREALLOCATION: u64 = 1024 * 1024;
+
+/// Reads the content of a ZIP entry with the given uncompressed `size`.
+///
+/// Reading fails if the entry yields more than `size` bytes. One byte more is
+/// requested from `reader`, so that the CRC-32 is still checked at its end.
+pub(crate) fn read_sized(reader: impl Read, size: u64) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::with_capacity(size.min(MAX_PREALLOCATION) as usize);
+    reader.take(size.saturating_add(1)).read_to_end(&mut data)?;
+    if data.len() as u64 > size {
+        return Err(Error::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "entry is larger than the size in its header",
+        )));
+    }
+    Ok(data)
+}
+
+/// Returns `true` if the archive path is inside the `pics/` directory.
+pub(crate) fn is_picture_path(name: &str) -> bool {
+    name.len() > 5 && name.is_char_boundary(5) && name[..5].eq_ignore_ascii_case("pics/")
+}
+
+/// Returns `true` if the archive path is the `pics/` directory entry itself or
+/// inside it.
+pub(crate) fn is_pics_dir_entry(name: &str) -> bool {
+    name.eq_ignore_ascii_case("pics/") || is_picture_path(name)
+}
+
+/// Returns the filename of the picture at `path` in the pics archive, or `None`
+/// if `path` is not a picture.
+///
+/// Pictures are stored in `pics/`, whose prefix is stripped from the filename.
+/// With `root_pictures`, image files at the top level of the archive are the
+/// pictures instead, and their path is the filename.
+pub(crate) fn picture_name(path: &str, root_pictures: bool) -> Option<&str> {
+    match root_pictures {
+        true => is_root_picture(path).then_some(path),
+        false => is_picture_path(path).then(|| &path[5..]),
+    }
+}
+
+/// Returns `true` if `path` is an image file at the top level of an archive.
+fn is_root_picture(path: &str) -> bool {
+    !path.contains('/')
+        && path
+            .rsplit_once('.')
+            .is_some_and(|(_, extension)| PictureFormat::from_extension(extension).is_some())
+}
+
+/// Reads `POINTS.CUP` from the points archive and returns the parsed CUP data,
+/// or the raw data if parsing is deferred, along with the header line.
+fn read_points_cup<R: Read + Seek>(
+    points_archive: &mut zip::ZipArchive<R>,
+    options: &ParseOptions,
+    warnings: &mut WarningList,
+) -> Result<(OnceLock<CupFile>, Option<DeferredCup>, String), Error> {
+    read_cup(points_archive.by_name("POINTS.CUP")?, options, warnings)
+}
+
+/// Reads the CUP data from `reader`, see [`read_points_cup`].
+fn read_cup(
+    reader: impl Read,
+    options: &ParseOptions,
+    warnings: &mut WarningList,
+) -> Result<(OnceLock<CupFile>, Option<DeferredCup>, String), Error> {
+    let mut points_cup = BufReader::new(reader);
+    let mut header = Vec::new();
+    points_cup.read_until(b'\n', &mut header)?;
+    let cup_header = header_line(&header);
+    let mut points_cup = header.as_slice().chain(points_cup);
+
+    if options.lazy_cup {
+        let mut data = Vec::new();
+        points_cup.read_to_end(&mut data)?;
+        Ok((OnceLock::new(), Some((data, options.clone())), cup_header))
+    } else {
+        let cup_file = parse_cup(points_cup, options, warnings)?;
+        Ok((OnceLock::from(cup_file), None, cup_header))
+    }
+}
+
+/// Returns the offset of the ZIP64 end of central directory records in front of
+/// the EOCD record at `eocd_offset`, or `eocd_offset` itself if there are none.
+///
+/// A ZIP64 archive stores a ZIP64 EOCD record and a ZIP64 EOCD locator directly
+/// before the regular EOCD record. Their fields may contain bytes that look like
+/// another EOCD signature, which must not be mistaken for the end of a preceding
+/// archive. The ZIP64 EOCD record is searched for within `max_size` bytes of
+/// the locator, and only accepted if its size field ends exactly at the locator.
+fn zip64_records_start<R: Read + Seek>(
+    reader: &mut R,
+    eocd_offset: u64,
+    max_size: u64,
+) -> Result<u64, Error> {
+    const LOCATOR_SIGNATURE: &[u8] = b"PK\x06\x07";
+    const LOCATOR_SIZE: u64 = 20;
+    const RECORD_SIGNATURE: &[u8] = b"PK\x06\x06";
+    // Signature and the size field, which doesn't count itself and the signature
+    const RECORD_HEADER_SIZE: u64 = 12;
+
+    let Some(locator_offset) = eocd_offset.checked_sub(LOCATOR_SIZE) else {
+        return Ok(eocd_offset);
+    };
+
+    let mut signature = [0u8; 4];
+    reader.seek(SeekFrom::Start(locator_offset))?;
+    reader.read_exact(&mut signature)?;
+    if signature != LOCATOR_SIGNATURE {
+        return Ok(eocd_offset);
+    }
+
+    let window_start = locator_offset.saturating_sub(max_size);
+    let mut window = vec![0u8; (locator_offset - window_start) as usize];
+    reader.seek(SeekFrom::Start(window_start))?;
+    reader.read_exact(&mut window)?;
+
+    let record_offset = memchr::memmem::rfind_iter(&window, RECORD_SIGNATURE).find(|&offset| {
+        let size = window
+            .get(offset + 4..offset + RECORD_HEADER_SIZE as usize)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()));
+        let record_end = (offset as u64 + RECORD_HEADER_SIZE).checked_add(size.unwrap_or(u64::MAX));
+        record_end == Some(window.len() as u64)
+    });
+
+    Ok(match record_offset {
+        Some(offset) => window_start + offset as u64,
+        None => locator_offset,
+    })
+}
+
+/// Compares the start of the points archive, as derived from its EOCD record,
+/// with the `boundary` at which the pics archive was found to end.
+///
+/// The central directory ends at `trailer_start`, where the ZIP64 records or the
+/// EOCD record begin, so the archive starts where the central directory offset
+/// counts from. This can't be checked if the EOCD record defers to the ZIP64
+/// records.
+fn check_archive_boundary<R: Read + Seek>(
+    reader: &mut R,
+    eocd_offset: u64,
+    trailer_start: u64,
+    boundary: u64,
+) -> Result<Option<Warning>, Error> {
+    reader.seek(SeekFrom::Start(eocd_offset))?;
+    let mut record = [0u8; Eocd::MIN_SIZE];
+    reader.read_exact(&mut record)?;
+    let eocd = parse_eocd(&record).ok_or(Error::InvalidCupx)?;
+
+    if eocd.central_directory_offset == u32::MAX || eocd.central_directory_size == u32::MAX {
+        return Ok(None);
+    }
+
+    let central_directory_end =
+        eocd.central_directory_offset as u64 + eocd.central_directory_size as u64;
+    let Some(points_start) = trailer_start.checked_sub(central_directory_end) else {
+        return Ok(None);
+    };
+
+    Ok(match points_start.cmp(&boundary) {
+        Ordering::Greater => Some(Warning::ArchiveGap {
+            bytes: points_start - boundary,
+        }),
+        Ordering::Less => Some(Warning::ArchiveOverlap {
+            bytes: boundary - points_start,
+        }),
+        Ordering::Equal => None,
+    })
+}
+
+/// Returns `true` if the given range of `reader` is a ZIP archive that contains
+/// a `POINTS.CUP` file.
+fn has_points_cup<R: Read + Seek>(reader: &mut R, range: Range<u64>) -> bool {
+    let Ok(points_reader) = LimitedReader::new(reader, range) else {
+        return false;
+    };
+    zip::ZipArchive::new(points_reader)
+        .is_ok_and(|mut archive| archive.by_name("POINTS.CUP").is_ok())
+}
+
+/// Scans the given range of `reader` for the local header of `POINTS.CUP` and
+/// returns the decompressed entry.
+///
+/// The last matching header is used, since the points archive comes last.
+fn recover_points_cup<R: Read + Seek>(reader: &mut R, range: Range<u64>) -> Result<Vec<u8>, Error> {
+    const LOCAL_HEADER_SIGNATURE: &[u8] = b"PK\x03\x04";
+    const LOCAL_HEADER_SIZE: usize = 30;
+
+    let mut buffer = Vec::new();
+    LimitedReader::new(reader, range)?.read_to_end(&mut buffer)?;
+
+    for offset in memchr::memmem::rfind_iter(&buffer, LOCAL_HEADER_SIGNATURE) {
+        let header = &buffer[offset..];
+        if header.len() < LOCAL_HEADER_SIZE {
+            continue;
+        }
+
+        let name_len = u16::from_le_bytes([header[26], header[27]]) as usize;
+        let name = header.get(LOCAL_HEADER_SIZE..LOCAL_HEADER_SIZE + name_len);
+        if !name.is_some_and(|name| name.eq_ignore_ascii_case(b"POINTS.CUP")) {
+            continue;
+        }
+
+        let mut cursor = Cursor::new(header);
+        if let Ok(Some(mut file)) = zip::read::read_zipfile_from_stream(&mut cursor) {
+            let mut data = Vec::new();
+            if file.read_to_end(&mut data).is_ok() {
+                return Ok(data);
+            }
+        }
+    }
+
+    Err(Error::InvalidCupx)
+}
+
+/// Indexes and optionally validates the pictures of a separate pics archive.
+///
+/// Also returns whether the pictures are stored at the top level of the archive,
+/// which is assumed if there are no `pics/` entries, but top-level image files.
+fn inspect_pics_archive<R: Read + Seek>(
+    pics_archive: &mut zip::ZipArchive<R>,
+    options: &ParseOptions,
+    warnings: &mut WarningList,
+) -> Result<(HashMap<String, PictureInfo>, bool), Error> {
+    let root_pictures = !pics_archive.file_names().any(is_picture_path)
+        && pics_archive.file_names().any(is_root_picture);
+    if root_pictures {
+        warnings.push(Warning::PicturesWithoutPrefix);
+    }
+
+    let picture_infos = index_pictures(pics_archive, root_pictures, warnings);
+
+    if options.eager_validate {
+        validate_pictures(
+            pics_archive,
+            root_pictures,
+            options.max_picture_size,
+            warnings,
+        )?;
+    }
+
+    if pics_archive
+        .file_names()
+        .any(|name| name.eq_ignore_ascii_case("POINTS.CUP"))
+    {
+        warnings.push(Warning::PointsCupInPicsArchive);
+    }
+
+    Ok((picture_infos, root_pictures))
+}
+
+/// Applies the strict mode and warning limit of `options` to the collected warnings.
+fn finish_warnings(warnings: WarningList, options: &ParseOptions) -> Result<Vec<Warning>, Error> {
+    if options.strict
+        && let Some(warning) = warnings.first()
+    {
+        return Err(Error::Strict(warning.clone()));
+    }
+
+    Ok(warnings.finish())
+}
+
+/// Decodes the header line of the CUP data, without line ending and BOM.
+///
+/// The header only contains ASCII column names, so any invalid UTF-8 is
+/// replaced instead of decoding it using the file's encoding.
+fn header_line(line: &[u8]) -> String {
+    let line = line.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(line);
+    String::from_utf8_lossy(line)
+        .trim_end_matches(['\r', '\n'])
+        .to_string()
+}
+
+/// Parses the CUP data and checks the result for out-of-range coordinates.
+fn parse_cup(
+    reader: impl Read,
+    options: &ParseOptions,
+    warnings: &mut WarningList,
+) -> Result<CupFile, Error> {
+    let (cup_file, cup_warnings) = match options.encoding {
+        Some(encoding) => CupFile::from_reader_with_encoding(reader, encoding)?,
+        None => CupFile::from_reader(reader)?,
+    };
+
+    if options.reject_replacement_chars {
+        let count = count_replacement_chars(&cup_file);
+        if count > 0 {
+            return Err(Error::EncodingReplacement { count });
+        }
+    }
+
+    warnings.extend(
+        cup_warnings
+            .into_iter()
+            .map(|issue| Warning::CupParseIssue {
+                message: issue.message().to_string(),
+                line: issue.line(),
+            }),
+    );
+    warnings.extend(
+        cup_file
+            .waypoints
+            .iter()
+            .filter(|waypoint| {
+                !(-90.0..=90.0).contains(&waypoint.latitude)
+                    || !(-180.0..=180.0).contains(&waypoint.longitude)
             })
+            .map(|waypoint| Warning::InvalidCoordinates {
+                name: waypoint.name.clone(),
+                latitude: waypoint.latitude,
+                longitude: waypoint.longitude,
+            }),
+    );
+    if cup_file.waypoints.is_empty() && cup_file.tasks.is_empty() {
+        warnings.push(Warning::EmptyWaypointData);
+    }
+
+    Ok(cup_file)
+}
+
+/// Counts the U+FFFD replacement characters in the text fields of `cup_file`.
+///
+/// The decoder inserts them for byte sequences that are invalid in the chosen
+/// encoding.
+fn count_replacement_chars(cup_file: &CupFile) -> usize {
+    fn count(text: &str) -> usize {
+        text.matches(char::REPLACEMENT_CHARACTER).count()
     }
+
+    fn count_waypoint(waypoint: &Waypoint) -> usize {
+        [
+            &waypoint.name,
+            &waypoint.code,
+            &waypoint.country,
+            &waypoint.frequency,
+            &waypoint.description,
+            &waypoint.userdata,
+        ]
+        .into_iter()
+        .chain(&waypoint.pictures)
+        .map(|text| count(text))
+        .sum()
+    }
+
+    let waypoints = cup_file.waypoints.iter().map(count_waypoint);
+    let tasks = cup_file.tasks.iter().map(|task| {
+        let options = task.options.as_ref();
+        task.description
+            .iter()
+            .chain(&task.waypoint_names)
+            .chain(&task.multiple_starts)
+            .chain(options.and_then(|options| options.no_start.as_ref()))
+            .chain(options.and_then(|options| options.task_time.as_ref()))
+            .map(|text| count(text))
+            .sum::<usize>()
+            + task
+                .points
+                .iter()
+                .map(|(_, waypoint)| count_waypoint(waypoint))
+                .sum::<usize>()
+    });
+
+    waypoints.chain(tasks).sum()
 }
@@ -0,0 +1,118 @@
+use crate::error::Error;
+use crate::reader::{CupxFile, is_pics_dir_entry, picture_name};
+use crate::writer::{default_file_options, sized_file_options};
+use std::io::{Cursor, Read, Seek, Write};
+
+/// Statistics about a [`repack`] operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepackStats {
+    /// Size of the source CUPX file in bytes.
+    pub original_size: u64,
+    /// Size of the repacked CUPX file in bytes.
+    pub new_size: u64,
+    /// Number of pictures that were decompressed and recompressed.
+    pub pictures_recompressed: usize,
+}
+
+/// Rewrites a CUPX file with all entries recompressed at the given deflate level.
+///
+/// Every entry of both archives is decompressed and written again using
+/// [`Deflated`](zip::CompressionMethod::Deflated) compression at `level`. The
+/// content of all entries, including `POINTS.CUP`, stays byte-identical, and
+/// the CUP data is never parsed. Files using the single-ZIP layout are
+/// rewritten using the classic layout of two concatenated archives.
+///
+/// Levels `1` to `9` use regular deflate, where `9` yields the smallest output.
+/// Levels `10` to `264` use the much slower Zopfli encoder for even smaller output.
+///
+/// # Examples
+///
+/// ```no_run
+/// use seeyou_cupx::{CupxFile, repack};
+/// use std::fs::File;
+///
+/// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+/// let stats = repack(&mut cupx, File::create("smaller.cupx")?, 9)?;
+/// println!("{} -> {} bytes", stats.original_size, stats.new_size);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the compression level is not supported, if an entry
+/// cannot be read, or if writing to the output fails. It also fails for files
+/// created with [`CupxFile::from_archives`].
+pub fn repack<R: Read + Seek, W: Write + Seek>(
+    src: &mut CupxFile<R>,
+    mut out: W,
+    level: i64,
+) -> Result<RepackStats, Error> {
+    let start = out.stream_position()?;
+
+    let options = default_file_options().compression_level(Some(level));
+
+    let single_zip = src.single_zip;
+    let root_pictures = src.root_pictures;
+    let mut pictures_recompressed = 0;
+    let mut pics_zip = zip::ZipWriter::new(out);
+
+    if let Some(pics_archive) = src.pics_archive.as_mut() {
+        for index in 0..pics_archive.len() {
+            let name = pics_archive.name_for_index(index).unwrap_or_default();
+
+            // In the single-ZIP layout, the CUP files are part of the pics archive
+            if single_zip && !is_pics_dir_entry(name) {
+                continue;
+            }
+
+            let is_picture = !name.ends_with('/') && picture_name(name, root_pictures).is_some();
+            recompress_entry(pics_archive, index, &mut pics_zip, options)?;
+            if is_picture {
+                pictures_recompressed += 1;
+            }
+        }
+    }
+
+    let mut out = pics_zip.finish()?;
+
+    let mut points_buffer = Vec::new();
+    let mut points_zip = zip::ZipWriter::new(Cursor::new(&mut points_buffer));
+    src.with_points_archive(|points_archive| {
+        for index in 0..points_archive.len() {
+            let name = points_archive.name_for_index(index).unwrap_or_default();
+            if !(single_zip && is_pics_dir_entry(name)) {
+                recompress_entry(points_archive, index, &mut points_zip, options)?;
+            }
+        }
+        Ok(())
+    })?;
+    points_zip.finish()?;
+    out.write_all(&points_buffer)?;
+
+    let end = out.stream_position()?;
+
+    Ok(RepackStats {
+        original_size: src.file_size,
+        new_size: end - start,
+        pictures_recompressed,
+    })
+}
+
+/// Decompresses the entry at `index` of `archive` and writes it to `zip`
+/// using `options`.
+fn recompress_entry<R: Read + Seek, W: Write + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    index: usize,
+    zip: &mut zip::ZipWriter<W>,
+    options: zip::write::FileOptions<()>,
+) -> Result<(), Error> {
+    let mut entry = archive.by_index(index)?;
+    let name = entry.name().to_string();
+    if entry.is_dir() {
+        zip.add_directory(name, options)?;
+    } else {
+        zip.start_file(name, sized_file_options(options, entry.size()))?;
+        std::io::copy(&mut entry, zip)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use crate::error::Error;
+use crate::reader::CupxFile;
+use crate::writer::default_file_options;
+use seeyou_cup::CupFile;
+use std::io::{Cursor, Read, Seek, Write};
+use zip::CompressionMethod;
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Writes this file with the current CUP data, keeping the pictures unchanged.
+    ///
+    /// This is the counterpart of editing the waypoints or tasks through
+    /// [`cup_file_mut`](Self::cup_file_mut) and friends. All entries of the pics
+    /// archive are copied without recompressing them, so pictures keep their
+    /// original compression method and stay byte-identical, while `POINTS.CUP`
+    /// is rewritten from the parsed CUP data. Other entries of the points
+    /// archive are copied unchanged.
+    ///
+    /// `POINTS.CUP` is stored uncompressed if it was stored uncompressed in the
+    /// source file, and deflated otherwise. The layout of the source file is
+    /// kept, but in the single-ZIP layout `POINTS.CUP` is moved to the end of
+    /// the archive.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// cupx.waypoints_mut().retain(|waypoint| waypoint.country == "CH");
+    /// cupx.write_preserving_compression(File::create("switzerland.cupx")?)?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::DeferredCupParse`] if the CUP data was parsed lazily
+    /// and parsing failed, or an error if reading from the source or writing
+    /// to the output fails, or if the file was created with
+    /// [`from_archives`](Self::from_archives).
+    pub fn write_preserving_compression(&mut self, out: impl Write + Seek) -> Result<(), Error> {
+        let mut cup_data = Vec::new();
+        self.checked_cup_file()?.to_writer(&mut cup_data)?;
+
+        let single_zip = self.single_zip;
+        let mut pics_zip = zip::ZipWriter::new(out);
+
+        if let Some(pics_archive) = self.pics_archive.as_mut() {
+            for index in 0..pics_archive.len() {
+                let entry = pics_archive.by_index_raw(index)?;
+                // Written by `rewrite_points_archive()`
+                if single_zip && is_points_cup(entry.name()) {
+                    continue;
+                }
+
+                pics_zip.raw_copy_file(entry)?;
+            }
+        }
+
+        self.rewrite_points_archive(pics_zip, &cup_data)
+    }
+
+    /// Finishes `pics_zip` and appends the points archive with `points_cup`
+    /// as the new content of `POINTS.CUP`.
+    ///
+    /// All other files of the points archive are copied unchanged. In the
+    /// single-ZIP layout, `POINTS.CUP` is added to `pics_zip` instead, so the
+    /// caller must skip the original one when copying the pics archive.
+    ///
+    /// `POINTS.CUP` is stored uncompressed if it was stored uncompressed in the
+    /// source file, and deflated otherwise.
+    pub(crate) fn rewrite_points_archive<W: Write + Seek>(
+        &mut self,
+        mut pics_zip: zip::ZipWriter<W>,
+        points_cup: &[u8],
+    ) -> Result<(), Error> {
+        if self.single_zip {
+            let compression = match self.pics_archive.as_mut() {
+                Some(archive) => points_cup_compression(archive)?,
+                None => CompressionMethod::Deflated,
+            };
+            pics_zip.start_file("POINTS.CUP", points_file_options(compression))?;
+            pics_zip.write_all(points_cup)?;
+            pics_zip.finish()?;
+            return Ok(());
+        }
+
+        let mut out = pics_zip.finish()?;
+
+        let points_buffer = self.with_points_archive(|points_archive| {
+            let compression = points_cup_compression(points_archive)?;
+
+            let mut points_buffer = Vec::new();
+            let mut points_zip = zip::ZipWriter::new(Cursor::new(&mut points_buffer));
+            points_zip.start_file("POINTS.CUP", points_file_options(compression))?;
+            points_zip.write_all(points_cup)?;
+            for index in 0..points_archive.len() {
+                let entry = points_archive.by_index_raw(index)?;
+                if !is_points_cup(entry.name()) {
+                    points_zip.raw_copy_file(entry)?;
+                }
+            }
+            points_zip.finish()?;
+            Ok(points_buffer)
+        })?;
+        out.write_all(&points_buffer)?;
+
+        Ok(())
+    }
+
+    /// Applies `transform` to the CUP data and writes the result, keeping the
+    /// pictures unchanged.
+    ///
+    /// This is a shortcut for editing the data through
+    /// [`cup_file_mut`](Self::cup_file_mut) and then calling
+    /// [`write_preserving_compression`](Self::write_preserving_compression), so
+    /// the pictures are copied without recompressing them. The transformed data
+    /// is kept in `self` afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    /// use std::fs::File;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// cupx.rewrite_with(
+    ///     |cup_file| {
+    ///         for waypoint in &mut cup_file.waypoints {
+    ///             waypoint.frequency.clear();
+    ///         }
+    ///     },
+    ///     File::create("redacted.cupx")?,
+    /// )?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in the same cases as
+    /// [`write_preserving_compression`](Self::write_preserving_compression).
+    pub fn rewrite_with<F: FnOnce(&mut CupFile)>(
+        &mut self,
+        transform: F,
+        out: impl Write + Seek,
+    ) -> Result<(), Error> {
+        transform(self.cup_file_mut());
+        self.write_preserving_compression(out)
+    }
+}
+
+/// Returns `true` if the archive path is `POINTS.CUP`, ignoring case.
+pub(crate) fn is_points_cup(name: &str) -> bool {
+    name.eq_ignore_ascii_case("POINTS.CUP")
+}
+
+/// Returns the compression method of `POINTS.CUP` in `archive`, or
+/// [`Deflated`](CompressionMethod::Deflated) if there is none.
+fn points_cup_compression<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+) -> Result<CompressionMethod, Error> {
+    let index =
+        (0..archive.len()).find(|&index| archive.name_for_index(index).is_some_and(is_points_cup));
+    match index {
+        Some(index) => Ok(archive.by_index_raw(index)?.compression()),
+        None => Ok(CompressionMethod::Deflated),
+    }
+}
+
+/// Returns the options for writing `POINTS.CUP`, keeping it uncompressed if it
+/// was stored uncompressed in the source file.
+fn points_file_options(compression: CompressionMethod) -> zip::write::FileOptions<'static, ()> {
+    match compression {
+        CompressionMethod::Stored => default_file_options().compression_method(compression),
+        _ => default_file_options(),
+    }
+}
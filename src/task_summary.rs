@@ -0,0 +1,15 @@
+/// A lightweight summary of a task, e.g. for listing tasks in a UI.
+///
+/// Returned by [`CupxFile::task_summaries`](crate::CupxFile::task_summaries).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TaskSummary {
+    /// Description of the task, or an empty string if it has none.
+    pub name: String,
+    /// Number of waypoints in the task, including takeoff and landing.
+    pub point_count: usize,
+    /// Total length of the task in meters, or `None` if not all of its points
+    /// could be resolved to waypoints, see
+    /// [`CupxFile::task_distance`](crate::CupxFile::task_distance).
+    pub distance: Option<f64>,
+}
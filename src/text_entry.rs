@@ -0,0 +1,130 @@
+use crate::error::Error;
+use crate::reader::CupxFile;
+use std::io::{Read, Seek};
+
+/// Maximum size of a text file read with [`CupxFile::read_text_entry`].
+///
+/// The size in the ZIP header can't be trusted, so reading stops with
+/// [`Error::EntryTooLarge`] once this many bytes have been decompressed.
+pub const MAX_TEXT_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Reads a text file stored next to `POINTS.CUP` in the points archive.
+    ///
+    /// Some authors put a `README` or `DETAILS.TXT` into the points archive to
+    /// describe the waypoint set. The name is matched case-insensitively
+    /// against the full path in the archive. The content is decoded as UTF-8,
+    /// and invalid sequences are replaced with U+FFFD. Use
+    /// [`read_text_entry_strict`](Self::read_text_entry_strict) to reject them
+    /// instead.
+    ///
+    /// Returns `None` if the points archive contains no such file. In the
+    /// single-ZIP layout, the points archive is the same as the pics archive.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// if let Some(details) = cupx.read_text_entry("DETAILS.TXT")? {
+    ///     println!("{details}");
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::EntryTooLarge`] if the file is larger than
+    /// [`MAX_TEXT_ENTRY_SIZE`], or an error if reading the archive fails, or if
+    /// the file was created with [`from_archives`](Self::from_archives).
+    pub fn read_text_entry(&mut self, name: &str) -> Result<Option<String>, Error> {
+        let data = self.read_points_entry(name, Some(MAX_TEXT_ENTRY_SIZE))?;
+        Ok(data.map(|data| String::from_utf8_lossy(&data).into_owned()))
+    }
+
+    /// Reads a text file stored next to `POINTS.CUP` in the points archive,
+    /// failing if it is not valid UTF-8.
+    ///
+    /// This is like [`read_text_entry`](Self::read_text_entry), but returns
+    /// [`Error::EncodingReplacement`] with the number of invalid sequences
+    /// instead of replacing them.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// if let Some(details) = cupx.read_text_entry_strict("DETAILS.TXT")? {
+    ///     println!("{details}");
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::EncodingReplacement`] if the file is not valid UTF-8,
+    /// and an error in the same cases as [`read_text_entry`](Self::read_text_entry).
+    pub fn read_text_entry_strict(&mut self, name: &str) -> Result<Option<String>, Error> {
+        let Some(data) = self.read_points_entry(name, Some(MAX_TEXT_ENTRY_SIZE))? else {
+            return Ok(None);
+        };
+
+        String::from_utf8(data).map(Some).map_err(|error| {
+            let count = error
+                .as_bytes()
+                .utf8_chunks()
+                .filter(|chunk| !chunk.invalid().is_empty())
+                .count();
+            Error::EncodingReplacement { count }
+        })
+    }
+
+    /// Returns the decompressed content of a file in the points archive,
+    /// matching `name` case-insensitively.
+    ///
+    /// Reading fails with [`Error::EntryTooLarge`] if the content exceeds
+    /// `max_size` bytes.
+    pub(crate) fn read_points_entry(
+        &mut self,
+        name: &str,
+        max_size: Option<u64>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        match self.pics_archive.as_mut() {
+            Some(archive) if self.single_zip => read_entry(archive, name, max_size),
+            _ => self.with_points_archive(|archive| read_entry(archive, name, max_size)),
+        }
+    }
+}
+
+/// Returns the content of the file with the given name, ignoring case.
+fn read_entry<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+    max_size: Option<u64>,
+) -> Result<Option<Vec<u8>>, Error> {
+    let index = (0..archive.len()).find(|&index| {
+        archive
+            .name_for_index(index)
+            .is_some_and(|path| !path.ends_with('/') && path.eq_ignore_ascii_case(name))
+    });
+    let Some(index) = index else {
+        return Ok(None);
+    };
+
+    let file = archive.by_index(index)?;
+    let path = file.name().to_string();
+    let limit = max_size.map_or(u64::MAX, |max_size| max_size.saturating_add(1));
+    let mut data = Vec::new();
+    file.take(limit).read_to_end(&mut data)?;
+    if let Some(max_size) = max_size
+        && data.len() as u64 > max_size
+    {
+        return Err(Error::EntryTooLarge {
+            name: path,
+            max_size,
+        });
+    }
+    Ok(Some(data))
+}
@@ -0,0 +1,104 @@
+use crate::error::Error;
+use crate::extract::enclosed_path;
+use crate::reader::{CupxFile, same_content};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek};
+use std::path::Path;
+
+/// A difference between the pictures of a CUPX file and a directory of originals.
+///
+/// Returned by [`CupxFile::verify_pictures_against_dir`]. All names are
+/// relative to the `pics/` directory of the archive and to the verified
+/// directory respectively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PictureMismatch {
+    /// The picture exists in both places, but the content differs.
+    ContentMismatch(String),
+    /// The picture exists in the archive, but not in the directory.
+    MissingOnDisk(String),
+    /// The file exists in the directory, but not in the archive.
+    MissingInArchive(String),
+}
+
+impl<R: Read + Seek> CupxFile<R> {
+    /// Compares the pictures of this file with the files in `dir`.
+    ///
+    /// Every picture is compared by its decompressed bytes with the file of the
+    /// same name in `dir`. Files in `dir` that have no picture of the same name
+    /// are reported as well, but only the top level of `dir` is checked for
+    /// these. Names are compared case-sensitively, like on most file systems.
+    /// Pictures whose name contains `..` or other components that would
+    /// leave `dir` are reported as [missing on disk](PictureMismatch::MissingOnDisk).
+    /// Both sides are streamed, so the pictures are never fully loaded into
+    /// memory.
+    ///
+    /// An empty result means that the archive contains exactly the files of `dir`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxFile;
+    ///
+    /// let (mut cupx, _) = CupxFile::from_path("waypoints.cupx")?;
+    /// for mismatch in cupx.verify_pictures_against_dir("photos")? {
+    ///     println!("{:?}", mismatch);
+    /// }
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a picture cannot be read, or if reading `dir` or one
+    /// of its files fails.
+    pub fn verify_pictures_against_dir(
+        &mut self,
+        dir: impl AsRef<Path>,
+    ) -> Result<Vec<PictureMismatch>, Error> {
+        let dir = dir.as_ref();
+        let names = self.picture_names().collect::<Vec<_>>();
+
+        let mut mismatches = Vec::new();
+        for name in &names {
+            // Names from the archive must not point outside of `dir`
+            let original = match enclosed_path(dir, name).map(File::open) {
+                Some(Ok(original)) => original,
+                Some(Err(error)) if error.kind() != ErrorKind::NotFound => {
+                    return Err(error.into());
+                }
+                _ => {
+                    mismatches.push(PictureMismatch::MissingOnDisk(name.clone()));
+                    continue;
+                }
+            };
+
+            let size = self.picture(name).map(|info| info.size);
+            let same = size == Some(original.metadata()?.len())
+                && same_content(
+                    BufReader::new(self.read_picture(name)?),
+                    BufReader::new(original),
+                )?;
+            if !same {
+                mismatches.push(PictureMismatch::ContentMismatch(name.clone()));
+            }
+        }
+
+        let names = names.iter().map(String::as_str).collect::<HashSet<_>>();
+        let mut extra = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if !names.contains(file_name.as_str()) {
+                extra.push(file_name);
+            }
+        }
+        extra.sort();
+        mismatches.extend(extra.into_iter().map(PictureMismatch::MissingInArchive));
+
+        Ok(mismatches)
+    }
+}
@@ -0,0 +1,58 @@
+use crate::error::Warning;
+
+/// A list of warnings that stops growing once it reaches a limit.
+///
+/// Warnings beyond the limit are only counted, so that malicious files with
+/// millions of issues can't exhaust memory. [`finish`](Self::finish) appends a
+/// [`Warning::WarningsTruncated`] if any warnings were dropped.
+#[derive(Debug)]
+pub(crate) struct WarningList {
+    warnings: Vec<Warning>,
+    max_len: usize,
+    total: usize,
+    /// The first warning if it was dropped, which happens with a limit of 0.
+    dropped_first: Option<Warning>,
+}
+
+impl WarningList {
+    /// Creates an empty list that keeps at most `max_len` warnings.
+    pub(crate) fn new(max_len: usize) -> Self {
+        Self {
+            warnings: Vec::new(),
+            max_len,
+            total: 0,
+            dropped_first: None,
+        }
+    }
+
+    /// Adds a warning, or only counts it if the list is full.
+    pub(crate) fn push(&mut self, warning: Warning) {
+        self.total += 1;
+        if self.warnings.len() < self.max_len {
+            self.warnings.push(warning);
+        } else if self.total == 1 {
+            self.dropped_first = Some(warning);
+        }
+    }
+
+    /// Returns the first warning that occurred, even if it was dropped.
+    pub(crate) fn first(&self) -> Option<&Warning> {
+        self.warnings.first().or(self.dropped_first.as_ref())
+    }
+
+    /// Returns the kept warnings, followed by a [`Warning::WarningsTruncated`]
+    /// if any were dropped.
+    pub(crate) fn finish(mut self) -> Vec<Warning> {
+        if self.total > self.warnings.len() {
+            self.warnings
+                .push(Warning::WarningsTruncated { total: self.total });
+        }
+        self.warnings
+    }
+}
+
+impl Extend<Warning> for WarningList {
+    fn extend<T: IntoIterator<Item = Warning>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|warning| self.push(warning));
+    }
+}
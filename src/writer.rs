@@ -1,9 +1,30 @@
-use crate::error::Error;
+#[cfg(feature = "sha2")]
+use crate::checksums::{HashingReader, format_manifest};
+use crate::error::{Error, WriterIssue};
+use crate::limited_writer::{LimitedWriter, map_size_limit_error};
+use crate::picture_format::PictureFormat;
+#[cfg(feature = "std")]
+use crate::reader::CupxFile;
 use seeyou_cup::CupFile;
+#[cfg(feature = "sha2")]
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+#[cfg(feature = "std")]
 use std::fs::File;
-use std::io::{Cursor, Seek, Write};
+use std::io::{Cursor, Read, Seek, Write};
+#[cfg(feature = "std")]
 use std::path::Path;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use zip::CompressionMethod;
+
+/// The default name under which [`CupxWriter::set_preview`] stores the preview
+/// image, relative to the `pics/` directory.
+pub const DEFAULT_PREVIEW_NAME: &str = "_preview.jpg";
+
+/// Name of the checksum manifest in the points archive.
+pub(crate) const CHECKSUMS_FILENAME: &str = "CHECKSUMS.txt";
 
 /// A builder for creating CUPX files with waypoint data and pictures.
 ///
@@ -26,18 +47,92 @@ use std::path::Path;
 /// ```
 pub struct CupxWriter<'a> {
     cup_file: &'a CupFile,
-    pictures: HashMap<&'a str, PictureSource<'a>>,
+    pictures: Vec<(&'a str, PictureSource<'a>)>,
+    /// Index of every filename in `pictures`, for replacing pictures quickly.
+    picture_indices: HashMap<&'a str, usize>,
+    points_files: Vec<(&'a str, &'a CupFile)>,
+    preview: Option<PictureSource<'a>>,
+    preview_name: &'a str,
+    layout: CupxLayout,
+    picture_order: PictureOrder,
+    write_directory_entries: bool,
+    max_output_size: Option<u64>,
+    picture_compression: CompressionMethod,
+    points_compression: CompressionMethod,
+    raw_points: Option<&'a [u8]>,
+    zip64: bool,
+    auto_compression: bool,
+    uppercase_pics_prefix: bool,
+    prefix_pictures: bool,
+    #[cfg(feature = "sha2")]
+    write_checksums: bool,
+}
+
+/// The archive layout of a written CUPX file.
+///
+/// See [`CupxWriter::layout`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CupxLayout {
+    /// Two concatenated ZIP archives, the first containing the pictures and the
+    /// second containing `POINTS.CUP`.
+    ///
+    /// This is the classic layout that is understood by all applications.
+    #[default]
+    Concatenated,
+    /// A single ZIP archive containing both the `pics/` directory and
+    /// `POINTS.CUP`.
+    ///
+    /// Some newer applications accept this layout, but older ones will not find
+    /// the pictures.
+    SingleZip,
+}
+
+/// The order in which pictures are written to the pics archive.
+///
+/// See [`CupxWriter::picture_order`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PictureOrder {
+    /// Pictures are written in the order they were added, followed by the
+    /// preview image.
+    #[default]
+    Insertion,
+    /// Pictures are written sorted by filename.
+    Alphabetical,
+    /// Pictures are written in the order of the given filenames. Pictures not
+    /// contained in the list are written afterwards in insertion order.
+    Custom(Vec<String>),
 }
 
 /// Source of picture data for inclusion in a CUPX file.
 ///
-/// Pictures can be provided either as in-memory byte slices or as file paths
-/// that will be read when the CUPX file is written.
+/// Pictures can be provided as in-memory byte slices, as file paths, as open
+/// files, or as pictures of other CUPX files. Paths, files and CUPX files are
+/// read when the CUPX file is written.
 pub enum PictureSource<'a> {
     /// Picture data provided as a borrowed byte slice.
     Bytes(&'a [u8]),
     /// Picture data will be read from a file at the given path.
+    #[cfg(feature = "std")]
     Path(&'a Path),
+    /// Picture data will be read from an already opened file.
+    ///
+    /// The file is read from its beginning, regardless of its current
+    /// position, so a temporary file that was just written can be added
+    /// without seeking back first. This also works for files without a path,
+    /// like unnamed temporary files.
+    #[cfg(feature = "std")]
+    File(&'a File),
+    /// Picture data will be read from a picture of another CUPX file.
+    ///
+    /// The source file is only opened while writing, and the picture is
+    /// streamed into the output, so it is never fully loaded into memory.
+    #[cfg(feature = "std")]
+    CupxEntry {
+        /// Path of the source CUPX file.
+        path: &'a Path,
+        /// Name of the picture in the source file, without the `pics/` prefix.
+        name: &'a str,
+    },
 }
 
 impl<'a> From<&'a [u8]> for PictureSource<'a> {
@@ -46,12 +141,20 @@ impl<'a> From<&'a [u8]> for PictureSource<'a> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a> From<&'a Path> for PictureSource<'a> {
     fn from(path: &'a Path) -> Self {
         PictureSource::Path(path)
     }
 }
 
+#[cfg(feature = "std")]
+impl<'a> From<&'a File> for PictureSource<'a> {
+    fn from(file: &'a File) -> Self {
+        PictureSource::File(file)
+    }
+}
+
 impl<'a> CupxWriter<'a> {
     /// Creates a new CUPX writer with the given waypoint/task data.
     ///
@@ -70,14 +173,37 @@ impl<'a> CupxWriter<'a> {
     pub fn new(cup_file: &'a CupFile) -> Self {
         Self {
             cup_file,
-            pictures: HashMap::new(),
+            pictures: Vec::new(),
+            picture_indices: HashMap::new(),
+            points_files: Vec::new(),
+            preview: None,
+            preview_name: DEFAULT_PREVIEW_NAME,
+            layout: CupxLayout::default(),
+            picture_order: PictureOrder::default(),
+            write_directory_entries: false,
+            max_output_size: None,
+            picture_compression: CompressionMethod::Deflated,
+            points_compression: CompressionMethod::Deflated,
+            raw_points: None,
+            zip64: false,
+            auto_compression: false,
+            uppercase_pics_prefix: false,
+            prefix_pictures: true,
+            #[cfg(feature = "sha2")]
+            write_checksums: false,
         }
     }
 
     /// Adds a picture to the CUPX file.
     ///
     /// The `filename` is the name the picture will have in the archive (without
-    /// the `pics/` prefix). The `source` can be either a file path or byte data.
+    /// the `pics/` prefix). The `source` can be a file path, an open file or byte
+    /// data.
+    /// Filenames containing path separators, including ones that already start
+    /// with `pics/`, are rejected with [`Error::InvalidFilename`] when writing.
+    ///
+    /// Adding a picture with the same filename twice replaces the first one,
+    /// but keeps its position in the insertion order.
     ///
     /// Returns a mutable reference to `self` for method chaining.
     ///
@@ -101,56 +227,593 @@ impl<'a> CupxWriter<'a> {
         filename: &'a str,
         source: impl Into<PictureSource<'a>>,
     ) -> &mut Self {
-        self.pictures.insert(filename, source.into());
+        let source = source.into();
+        match self.picture_indices.entry(filename) {
+            Entry::Occupied(entry) => self.pictures[*entry.get()].1 = source,
+            Entry::Vacant(entry) => {
+                entry.insert(self.pictures.len());
+                self.pictures.push((filename, source));
+            }
+        }
         self
     }
 
-    /// Writes the CUPX file to the given writer.
+    /// Adds a picture that is copied from another CUPX file.
     ///
-    /// The writer must implement both [`Write`] and [`Seek`].
+    /// This is a shortcut for [`add_picture`](Self::add_picture) with a
+    /// [`PictureSource::CupxEntry`]. The source file is opened when writing and
+    /// the picture is streamed into the output, which keeps memory usage flat
+    /// when deriving a file from the pictures of an existing one.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    /// use std::path::Path;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file)
+    ///     .add_picture_from_cupx("photo.jpg", Path::new("source.cupx"), "IMG_0042.jpg")
+    ///     .write_to_path("derived.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn add_picture_from_cupx(
+        &mut self,
+        filename: &'a str,
+        src_path: &'a Path,
+        src_entry: &'a str,
+    ) -> &mut Self {
+        let source = PictureSource::CupxEntry {
+            path: src_path,
+            name: src_entry,
+        };
+        self.add_picture(filename, source)
+    }
+
+    /// Sets the preview image of the CUPX file.
+    ///
+    /// Some applications show a specific picture as the thumbnail of a CUPX
+    /// file. The preview is stored as a regular picture named
+    /// [`DEFAULT_PREVIEW_NAME`], unless a different name is configured with
+    /// [`preview_name`](Self::preview_name). It replaces any picture added
+    /// under the same name, and can be read back with
+    /// [`CupxFile::preview`](crate::CupxFile::preview).
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    /// # use std::path::Path;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file)
+    ///     .set_preview(Path::new("images/cover.jpg"))
+    ///     .write_to_path("output.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn set_preview(&mut self, source: impl Into<PictureSource<'a>>) -> &mut Self {
+        self.preview = Some(source.into());
+        self
+    }
+
+    /// Sets the name under which the preview image is stored.
+    ///
+    /// Defaults to [`DEFAULT_PREVIEW_NAME`]. The name is relative to the
+    /// `pics/` directory and is subject to the same rules as other picture
+    /// filenames.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    pub fn preview_name(&mut self, name: &'a str) -> &mut Self {
+        self.preview_name = name;
+        self
+    }
+
+    /// Sets the archive layout of the written file.
+    ///
+    /// Defaults to [`CupxLayout::Concatenated`].
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::{CupxLayout, CupxWriter};
+    /// use seeyou_cup::CupFile;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file)
+    ///     .layout(CupxLayout::SingleZip)
+    ///     .write_to_path("output.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn layout(&mut self, layout: CupxLayout) -> &mut Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets the order in which pictures are written to the pics archive.
+    ///
+    /// Defaults to [`PictureOrder::Insertion`]. Some applications read
+    /// pictures sequentially, so putting frequently used pictures first can
+    /// speed up loading.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::{CupxWriter, PictureOrder};
+    /// use seeyou_cup::CupFile;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file)
+    ///     .picture_order(PictureOrder::Alphabetical)
+    ///     .write_to_path("output.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn picture_order(&mut self, order: PictureOrder) -> &mut Self {
+        self.picture_order = order;
+        self
+    }
+
+    /// Sets whether an explicit `pics/` directory entry is written before the
+    /// pictures.
+    ///
+    /// Most tools infer the directory from the picture paths, but some strict
+    /// ZIP consumers only list directories that have their own entry. Disabled
+    /// by default, which keeps the output identical to previous versions.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    pub fn write_directory_entries(&mut self, enabled: bool) -> &mut Self {
+        self.write_directory_entries = enabled;
+        self
+    }
+
+    /// Sets whether the pictures are written to `PICS/` instead of `pics/`.
+    ///
+    /// The prefix is matched case-insensitively when reading, so this only
+    /// matters for tools that expect the uppercase prefix, e.g. to reproduce
+    /// files from exporters that write `PICS/`. It also applies to the
+    /// [directory entry](Self::write_directory_entries).
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    pub fn pics_prefix_case(&mut self, upper: bool) -> &mut Self {
+        self.uppercase_pics_prefix = upper;
+        self
+    }
+
+    /// Sets whether the pictures are written into the `pics/` directory.
+    ///
+    /// Enabled by default. When disabled, the filenames are used as entry
+    /// paths verbatim, and may contain `/` to place pictures in subfolders.
+    /// This allows writing the nonstandard layouts that the reader tolerates,
+    /// like pictures at the top level of the pics archive, e.g. for testing
+    /// other applications. The paths must still be relative, without empty,
+    /// `.` or `..` components, and must not be `POINTS.CUP`. No
+    /// [directory entry](Self::write_directory_entries) is written.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file)
+    ///     .add_picture("photos/airport.jpg", &b"..."[..])
+    ///     .prefix_pictures(false)
+    ///     .write_to_path("output.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn prefix_pictures(&mut self, enabled: bool) -> &mut Self {
+        self.prefix_pictures = enabled;
+        self
+    }
+
+    /// Sets the maximum size of the written CUPX file in bytes.
+    ///
+    /// The limit applies to the combined size of both archives. Writing is
+    /// aborted with [`Error::OutputTooLarge`] as soon as the limit would be
+    /// exceeded, so that a file that is too large is never completed. The
+    /// partially written output should be discarded in that case.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// let bytes = CupxWriter::new(&cup_file)
+    ///     .max_output_size(10 * 1024 * 1024)
+    ///     .write_to_vec()?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn max_output_size(&mut self, bytes: u64) -> &mut Self {
+        self.max_output_size = Some(bytes);
+        self
+    }
+
+    /// Sets the compression method of the pictures.
+    ///
+    /// Defaults to [`Deflated`](CompressionMethod::Deflated). Since JPEG and PNG
+    /// images are already compressed, storing them with
+    /// [`Stored`](CompressionMethod::Stored) saves time while writing and
+    /// reading at the cost of a slightly larger file. This is independent of
+    /// the [compression of the CUP files](Self::points_compression).
+    ///
+    /// Only `Stored` and `Deflated` are supported. Writing fails with an
+    /// [`Error::Zip`] for any other method.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    /// use zip::CompressionMethod;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file)
+    ///     .picture_compression(CompressionMethod::Stored)
+    ///     .write_to_path("output.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn picture_compression(&mut self, method: CompressionMethod) -> &mut Self {
+        self.picture_compression = method;
+        self
+    }
+
+    /// Sets whether the compression method is chosen per picture based on its
+    /// format.
+    ///
+    /// When enabled, the first bytes of every picture are inspected while
+    /// writing. Formats that are already compressed (JPEG, PNG, GIF and WebP)
+    /// are [`Stored`](CompressionMethod::Stored), since deflating them again
+    /// costs time without making them meaningfully smaller. All other pictures,
+    /// like BMP or text files, are [`Deflated`](CompressionMethod::Deflated).
+    /// This overrides the [picture compression](Self::picture_compression).
+    ///
+    /// Disabled by default.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    /// use std::path::Path;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file)
+    ///     .add_picture("photo.jpg", Path::new("images/photo.jpg"))
+    ///     .add_picture("map.bmp", Path::new("images/map.bmp"))
+    ///     .auto_compression(true)
+    ///     .write_to_path("output.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    pub fn auto_compression(&mut self, enabled: bool) -> &mut Self {
+        self.auto_compression = enabled;
+        self
+    }
+
+    /// Sets the compression method of `POINTS.CUP` and any supplementary CUP files.
+    ///
+    /// Defaults to [`Deflated`](CompressionMethod::Deflated). For large
+    /// waypoint sets, compressing the CUP data takes up most of the time spent
+    /// writing, which [`Stored`](CompressionMethod::Stored) avoids at the cost
+    /// of a larger file. This is independent of the
+    /// [compression of the pictures](Self::picture_compression).
+    ///
+    /// Like for the pictures, only `Stored` and `Deflated` are supported.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    pub fn points_compression(&mut self, method: CompressionMethod) -> &mut Self {
+        self.points_compression = method;
+        self
+    }
+
+    /// Sets whether ZIP64 extensions are written for all entries.
+    ///
+    /// By default, ZIP64 extensions are only written where the classic ZIP
+    /// format runs out of space: for pictures of 4 GiB or more, and for the
+    /// end of central directory record if the pics archive grows beyond 4 GiB
+    /// or 65535 entries. Files without such large content therefore stay
+    /// readable by applications without ZIP64 support.
+    ///
+    /// When enabled, every entry gets ZIP64 size fields, even if it's small.
+    /// This is only needed for consumers that expect ZIP64 throughout.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    pub fn zip64(&mut self, enabled: bool) -> &mut Self {
+        self.zip64 = enabled;
+        self
+    }
+
+    /// Sets whether a `CHECKSUMS.txt` manifest is written into the points
+    /// archive.
+    ///
+    /// The manifest lists the SHA-256 digest of every picture in the format of
+    /// `sha256sum`, and can be checked with
+    /// [`CupxFile::verify_checksums`](crate::CupxFile::verify_checksums). The
+    /// digests are computed while the pictures are written, so the pictures
+    /// are only read once. Disabled by default.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    /// # use std::path::Path;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file)
+    ///     .add_picture("photo.jpg", Path::new("images/photo.jpg"))
+    ///     .write_checksums(true)
+    ///     .write_to_path("output.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    #[cfg(feature = "sha2")]
+    pub fn write_checksums(&mut self, enabled: bool) -> &mut Self {
+        self.write_checksums = enabled;
+        self
+    }
+
+    /// Sets the content of `POINTS.CUP` to the given bytes.
+    ///
+    /// By default, `POINTS.CUP` is serialized from the [`CupFile`] passed to
+    /// [`new`](Self::new). With raw points, the bytes are written verbatim
+    /// instead, and the `CupFile` is ignored. This allows writing CUP data with
+    /// content that `seeyou_cup` doesn't model, like vendor-specific columns.
+    /// The bytes are not validated beyond not being empty. Pictures and
+    /// supplementary CUP files are written as usual.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    ///
+    /// let points = std::fs::read("custom.cup")?;
+    /// CupxWriter::new(&CupFile::default())
+    ///     .set_raw_points(&points)
+    ///     .write_to_path("output.cupx")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_raw_points(&mut self, bytes: &'a [u8]) -> &mut Self {
+        self.raw_points = Some(bytes);
+        self
+    }
+
+    /// Adds a supplementary CUP file to the points archive.
+    ///
+    /// The main waypoint/task data is always written as `POINTS.CUP`. Some
+    /// applications bundle additional waypoint sets as further `*.cup` files in
+    /// the points archive, which can be added with this method. Adding a file
+    /// with the same name twice replaces the first one.
+    ///
+    /// Returns a mutable reference to `self` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// # let outlandings = CupFile::default();
+    /// CupxWriter::new(&cup_file)
+    ///     .add_points_file("OUTLANDINGS.CUP", &outlandings)?
+    ///     .write_to_path("output.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - Any picture filename is invalid (empty or contains path separators)
-    /// - A picture file cannot be read
-    /// - Writing to the output fails
-    pub fn write<W: Write + Seek>(&self, writer: W) -> Result<(), Error> {
-        for filename in self.pictures.keys() {
-            if filename.is_empty() || filename.contains('/') || filename.contains('\\') {
-                return Err(Error::InvalidFilename(filename.to_string()));
-            }
+    /// Returns [`Error::InvalidPointsFilename`] if the name is empty, contains
+    /// path separators, does not end in `.cup`, or is `POINTS.CUP` (all
+    /// case-insensitive).
+    pub fn add_points_file(
+        &mut self,
+        filename: &'a str,
+        cup_file: &'a CupFile,
+    ) -> Result<&mut Self, Error> {
+        let is_cup = filename.len() > 4
+            && filename.is_char_boundary(filename.len() - 4)
+            && filename[filename.len() - 4..].eq_ignore_ascii_case(".cup");
+
+        if !is_cup
+            || filename.contains('/')
+            || filename.contains('\\')
+            || filename.eq_ignore_ascii_case("POINTS.CUP")
+        {
+            return Err(Error::InvalidPointsFilename(filename.to_string()));
         }
 
-        let options = zip::write::FileOptions::<()>::default()
-            .compression_method(zip::CompressionMethod::Deflated);
+        match self
+            .points_files
+            .iter_mut()
+            .find(|(name, _)| *name == filename)
+        {
+            Some(entry) => entry.1 = cup_file,
+            None => self.points_files.push((filename, cup_file)),
+        }
 
-        let mut pics_zip = zip::ZipWriter::new(writer);
+        Ok(self)
+    }
 
-        for (filename, source) in &self.pictures {
-            let zip_filename = format!("pics/{}", filename);
-            pics_zip.start_file(&zip_filename, options)?;
+    /// Checks the configured pictures and CUP data for problems without
+    /// writing anything.
+    ///
+    /// All problems are returned at once, e.g. for presenting them together in
+    /// a UI. [`write`](Self::write) runs the same checks and fails on the first
+    /// issue that [is an error](WriterIssue::is_error). Picture files are only
+    /// checked for existence, not read.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    /// use std::path::Path;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// let mut writer = CupxWriter::new(&cup_file);
+    /// writer.add_picture("photo.jpg", Path::new("images/photo.jpg"));
+    /// for issue in writer.validate() {
+    ///     println!("{issue:?}");
+    /// }
+    /// ```
+    pub fn validate(&self) -> Vec<WriterIssue> {
+        let mut issues = Vec::new();
+
+        let mut names = HashMap::<_, &str>::new();
+        for (filename, source) in self.pictures_to_write() {
+            let valid = match self.prefix_pictures {
+                true => validate_filename(filename),
+                false => validate_entry_path(filename),
+            };
+            if valid.is_err() {
+                issues.push(WriterIssue::InvalidFilename(filename.to_string()));
+            }
 
-            match source {
-                PictureSource::Bytes(data) => {
-                    pics_zip.write_all(data)?;
+            match names.entry(filename.to_lowercase()) {
+                Entry::Occupied(entry) => issues.push(WriterIssue::CaseCollision {
+                    first: entry.get().to_string(),
+                    second: filename.to_string(),
+                }),
+                Entry::Vacant(entry) => {
+                    entry.insert(filename);
                 }
-                PictureSource::Path(path) => {
-                    let mut file = File::open(path)?;
-                    std::io::copy(&mut file, &mut pics_zip)?;
+            }
+
+            #[cfg(feature = "std")]
+            if let PictureSource::Path(path) | PictureSource::CupxEntry { path, .. } = source
+                && !path.is_file()
+            {
+                issues.push(WriterIssue::MissingFile {
+                    name: filename.to_string(),
+                    path: path.to_path_buf(),
+                });
+            }
+            #[cfg(not(feature = "std"))]
+            let _ = source;
+        }
+
+        match self.raw_points {
+            Some([]) => issues.push(WriterIssue::EmptyRawPoints),
+            Some(_) => {}
+            None => {
+                for waypoint in &self.cup_file.waypoints {
+                    for picture in &waypoint.pictures {
+                        if !names.contains_key(&picture.to_lowercase()) {
+                            issues.push(WriterIssue::DanglingReference {
+                                waypoint: waypoint.name.clone(),
+                                picture: picture.clone(),
+                            });
+                        }
+                    }
                 }
             }
         }
 
-        let mut writer = pics_zip.finish()?;
+        issues
+    }
 
-        let mut points_buffer = Vec::new();
-        let mut points_zip = zip::ZipWriter::new(Cursor::new(&mut points_buffer));
-        points_zip.start_file("POINTS.CUP", options)?;
-        self.cup_file.to_writer(&mut points_zip)?;
-        points_zip.finish()?;
-        writer.write_all(&points_buffer)?;
+    /// Writes the CUPX file to the given writer.
+    ///
+    /// The writer must implement both [`Write`] and [`Seek`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Any picture filename is invalid (empty or contains path separators)
+    /// - A picture file cannot be read
+    /// - Writing to the output fails
+    /// - The output exceeds the [maximum size](Self::max_output_size)
+    pub fn write<W: Write + Seek>(&self, mut writer: W) -> Result<(), Error> {
+        let start = writer.stream_position()?;
+        let writer = LimitedWriter::new(writer, start, self.max_output_size);
+        self.write_archives(zip::ZipWriter::new(writer), true)
+            .map_err(map_size_limit_error)?;
+        Ok(())
+    }
+
+    /// Writes the CUPX file to the given writer without validating the input
+    /// first.
+    ///
+    /// This is like [`write`](Self::write), but skips the checks of
+    /// [`validate`](Self::validate), including the filename checks and the
+    /// existence checks of picture files. It is meant for hot paths where the
+    /// filenames were generated or validated beforehand.
+    ///
+    /// Only use this for trusted input. Invalid filenames are written into the
+    /// archive as they are, so entries may end up with unexpected paths, e.g.
+    /// outside of the `pics/` directory, or colliding with `POINTS.CUP`. Empty
+    /// [raw points](Self::set_raw_points) are written as an empty `POINTS.CUP`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - A picture file cannot be read
+    /// - Writing to the output fails
+    /// - The output exceeds the [maximum size](Self::max_output_size)
+    pub fn write_unchecked<W: Write + Seek>(&self, mut writer: W) -> Result<(), Error> {
+        let start = writer.stream_position()?;
+        let writer = LimitedWriter::new(writer, start, self.max_output_size);
+        self.write_archives(zip::ZipWriter::new(writer), false)
+            .map_err(map_size_limit_error)?;
+        Ok(())
+    }
 
+    /// Writes the CUPX file to a writer that doesn't support seeking.
+    ///
+    /// Unlike [`write`](Self::write), this only requires [`Write`], which makes it
+    /// suitable for network sockets or pipes. The ZIP entries are written in
+    /// streaming mode, where sizes and checksums follow the data in data
+    /// descriptors instead of being patched into the local headers afterwards.
+    /// The result can be read with [`CupxFile::from_reader`](crate::CupxFile::from_reader)
+    /// like any other CUPX file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    ///
+    /// # let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file).write_streaming(std::io::stdout())?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Any picture filename is invalid (empty or contains path separators)
+    /// - A picture file cannot be read
+    /// - Writing to the output fails
+    /// - The output exceeds the [maximum size](Self::max_output_size)
+    pub fn write_streaming<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let writer = LimitedWriter::new(writer, 0, self.max_output_size);
+        self.write_archives(zip::ZipWriter::new_stream(writer), true)
+            .map_err(map_size_limit_error)?;
         Ok(())
     }
 
@@ -172,8 +835,9 @@ impl<'a> CupxWriter<'a> {
     ///
     /// # Errors
     ///
-    /// Returns an error if any picture filename is invalid or if a picture
-    /// file cannot be read.
+    /// Returns an error if any picture filename is invalid, if a picture file
+    /// cannot be read, or if the output exceeds the
+    /// [maximum size](Self::max_output_size).
     pub fn write_to_vec(&self) -> Result<Vec<u8>, Error> {
         let mut buffer = Vec::new();
         self.write(Cursor::new(&mut buffer))?;
@@ -200,8 +864,505 @@ impl<'a> CupxWriter<'a> {
     /// - Any picture filename is invalid
     /// - A picture file cannot be read
     /// - Writing to the output fails
+    /// - The output exceeds the [maximum size](Self::max_output_size)
+    #[cfg(feature = "std")]
     pub fn write_to_path(&self, path: impl AsRef<Path>) -> Result<(), Error> {
         let file = File::create(path)?;
         self.write(file)
     }
+
+    /// Writes the CUPX file to the given path without ever exposing a partially
+    /// written file.
+    ///
+    /// The file is first written to a temporary file next to `path`, which is
+    /// synced to disk and then renamed over `path`. Since the rename replaces
+    /// the file atomically, readers either see the previous content or the
+    /// complete new file, even if the process crashes while writing. On Unix,
+    /// the parent directory is synced after the rename, so that the new file
+    /// also survives a power loss. The temporary file is removed if writing
+    /// fails.
+    ///
+    /// Every call uses its own temporary file, so multiple threads may write
+    /// to the same path at once. The last rename wins.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use seeyou_cupx::CupxWriter;
+    /// use seeyou_cup::CupFile;
+    ///
+    /// let cup_file = CupFile::default();
+    /// CupxWriter::new(&cup_file).write_to_path_atomic("output.cupx")?;
+    /// # Ok::<(), seeyou_cupx::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in the same cases as [`write_to_path`](Self::write_to_path),
+    /// or if the temporary file can't be synced or renamed.
+    #[cfg(feature = "std")]
+    pub fn write_to_path_atomic(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let filename = path.file_name().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name")
+        })?;
+
+        // The counter keeps concurrent writers in this process apart, and skips
+        // temporary files left behind by a crashed process with the same pid
+        let (temp_path, mut file) = loop {
+            let counter = TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let mut temp_name = std::ffi::OsString::from(".");
+            temp_name.push(filename);
+            temp_name.push(format!(".{}.{counter}.tmp", std::process::id()));
+            let temp_path = path.with_file_name(temp_name);
+
+            match File::options()
+                .write(true)
+                .create_new(true)
+                .open(&temp_path)
+            {
+                Ok(file) => break (temp_path, file),
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(error.into()),
+            }
+        };
+
+        let result = self
+            .write(&mut file)
+            .and_then(|()| Ok(file.sync_all()?))
+            .and_then(|()| Ok(std::fs::rename(&temp_path, path)?));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+        result?;
+
+        // Persist the rename itself, which is stored in the parent directory
+        #[cfg(unix)]
+        {
+            let parent = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty());
+            File::open(parent.unwrap_or(Path::new(".")))?.sync_all()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes all pictures and CUP files using the configured layout.
+    ///
+    /// The input is only checked with [`validate`](Self::validate) if
+    /// `validate` is set.
+    fn write_archives<W: Write + Seek>(
+        &self,
+        mut zip: zip::ZipWriter<W>,
+        validate: bool,
+    ) -> Result<W, Error> {
+        let issues = if validate {
+            self.validate()
+        } else {
+            Vec::new()
+        };
+        for issue in issues {
+            match issue {
+                WriterIssue::InvalidFilename(filename) => {
+                    return Err(Error::InvalidFilename(filename));
+                }
+                WriterIssue::EmptyRawPoints => return Err(Error::EmptyRawPoints),
+                // Missing files are reported with the original I/O error when
+                // they are opened
+                _ => {}
+            }
+        }
+
+        let pictures = self.pictures_to_write();
+        let points_cup = match self.raw_points {
+            Some(bytes) => PointsCup::Raw(bytes),
+            None => PointsCup::Parsed(self.cup_file),
+        };
+
+        let options = default_file_options()
+            .compression_method(self.picture_compression)
+            .large_file(self.zip64);
+        let points_options = default_file_options()
+            .compression_method(self.points_compression)
+            .large_file(self.zip64);
+
+        if self.write_directory_entries && self.prefix_pictures {
+            zip.add_directory(self.pics_prefix(), options)?;
+        }
+        #[cfg(feature = "sha2")]
+        let mut checksums = self.write_checksums.then(Vec::new);
+        #[cfg(not(feature = "sha2"))]
+        let mut checksums = None;
+
+        let prefix = self.pics_prefix();
+        write_pictures(
+            &mut zip,
+            pictures,
+            prefix,
+            options,
+            self.auto_compression,
+            checksums.as_mut(),
+        )?;
+
+        #[cfg(feature = "sha2")]
+        let manifest = checksums.map(|checksums| format_manifest(&checksums));
+        #[cfg(not(feature = "sha2"))]
+        let manifest: Option<String> = None;
+
+        let extra_files = &self.points_files;
+        let manifest = manifest.as_deref();
+        match self.layout {
+            CupxLayout::Concatenated => {
+                let mut writer = zip.finish()?;
+                write_points_archive(
+                    &mut writer,
+                    points_cup,
+                    extra_files,
+                    manifest,
+                    points_options,
+                )?;
+                Ok(writer)
+            }
+            CupxLayout::SingleZip => {
+                write_points_files(&mut zip, points_cup, extra_files, manifest, points_options)?;
+                Ok(zip.finish()?)
+            }
+        }
+    }
+
+    /// Returns the prefix of the picture paths in the pics archive.
+    fn pics_prefix(&self) -> &'static str {
+        match (self.prefix_pictures, self.uppercase_pics_prefix) {
+            (false, _) => "",
+            (true, true) => "PICS/",
+            (true, false) => "pics/",
+        }
+    }
+
+    /// Returns the pictures to write in the configured order, with the preview
+    /// replacing any picture of the same name.
+    fn pictures_to_write(&self) -> Vec<(&'a str, &PictureSource<'a>)> {
+        let mut pictures = self
+            .pictures
+            .iter()
+            .filter(|(filename, _)| self.preview.is_none() || *filename != self.preview_name)
+            .map(|(filename, source)| (*filename, source))
+            .collect::<Vec<_>>();
+
+        if let Some(preview) = &self.preview {
+            pictures.push((self.preview_name, preview));
+        }
+
+        match &self.picture_order {
+            PictureOrder::Insertion => {}
+            PictureOrder::Alphabetical => pictures.sort_by_key(|(filename, _)| *filename),
+            PictureOrder::Custom(order) => {
+                let mut positions = HashMap::with_capacity(order.len());
+                for (position, name) in order.iter().enumerate() {
+                    positions.entry(name.as_str()).or_insert(position);
+                }
+                pictures.sort_by_key(|(filename, _)| {
+                    positions.get(filename).copied().unwrap_or(order.len())
+                });
+            }
+        }
+
+        pictures
+    }
+}
+
+/// A streaming writer for creating CUPX files without collecting pictures first.
+///
+/// Unlike [`CupxWriter`], which holds all picture sources until
+/// [`write`](CupxWriter::write) is called, `CupxStreamWriter` appends each picture
+/// to the pics archive immediately. Once all pictures are written,
+/// [`finish_pictures_and_write_cup`](Self::finish_pictures_and_write_cup) closes the
+/// pics archive and appends the points archive.
+///
+/// # Examples
+///
+/// ```no_run
+/// use seeyou_cupx::CupxStreamWriter;
+/// use seeyou_cup::CupFile;
+/// use std::fs::File;
+///
+/// # let cup_file = CupFile::default();
+/// let mut writer = CupxStreamWriter::new(File::create("output.cupx")?);
+/// writer.write_picture("photo1.jpg", File::open("images/photo1.jpg")?)?;
+/// writer.write_picture("photo2.jpg", File::open("images/photo2.jpg")?)?;
+/// writer.finish_pictures_and_write_cup(&cup_file)?;
+/// # Ok::<(), seeyou_cupx::Error>(())
+/// ```
+pub struct CupxStreamWriter<W: Write + Seek> {
+    pics_zip: zip::ZipWriter<W>,
+}
+
+impl<W: Write + Seek> CupxStreamWriter<W> {
+    /// Creates a new streaming CUPX writer that writes to the given writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            pics_zip: zip::ZipWriter::new(writer),
+        }
+    }
+
+    /// Writes a picture to the pics archive immediately.
+    ///
+    /// The `filename` is the name the picture will have in the archive (without
+    /// the `pics/` prefix). The picture data is copied from `reader`.
+    ///
+    /// Unlike [`CupxWriter::add_picture`], writing the same filename twice results
+    /// in an error from the underlying ZIP writer.
+    ///
+    /// Since the size of the picture is not known in advance, every picture is
+    /// written with ZIP64 extensions, so that pictures of 4 GiB or more don't
+    /// fail halfway through.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The filename is invalid (empty or contains path separators)
+    /// - Reading from `reader` fails
+    /// - Writing to the output fails
+    pub fn write_picture(&mut self, filename: &str, mut reader: impl Read) -> Result<(), Error> {
+        validate_filename(filename)?;
+
+        let zip_filename = format!("pics/{}", filename);
+        let options = default_file_options().large_file(true);
+        self.pics_zip.start_file(&zip_filename, options)?;
+        std::io::copy(&mut reader, &mut self.pics_zip)?;
+        Ok(())
+    }
+
+    /// Closes the pics archive and appends the points archive with the given
+    /// waypoint/task data.
+    ///
+    /// Returns the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the output fails.
+    pub fn finish_pictures_and_write_cup(self, cup_file: &CupFile) -> Result<W, Error> {
+        let mut writer = self.pics_zip.finish()?;
+        let points_cup = PointsCup::Parsed(cup_file);
+        write_points_archive(&mut writer, points_cup, &[], None, default_file_options())?;
+        Ok(writer)
+    }
+}
+
+/// Counter for unique temporary filenames in
+/// [`write_to_path_atomic`](CupxWriter::write_to_path_atomic).
+#[cfg(feature = "std")]
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the ZIP file options used for all entries written by this crate.
+pub(crate) fn default_file_options() -> zip::write::FileOptions<'static, ()> {
+    zip::write::FileOptions::<()>::default().compression_method(CompressionMethod::Deflated)
+}
+
+/// Checks that a picture filename is not empty and contains no path separators.
+pub(crate) fn validate_filename(filename: &str) -> Result<(), Error> {
+    if filename.is_empty() || filename.contains('/') || filename.contains('\\') {
+        return Err(Error::InvalidFilename(filename.to_string()));
+    }
+    Ok(())
+}
+
+/// Checks that an unprefixed picture path is relative and contains no empty,
+/// `.` or `..` components, and that it doesn't collide with `POINTS.CUP`.
+fn validate_entry_path(path: &str) -> Result<(), Error> {
+    let invalid = path.contains('\\')
+        || path.eq_ignore_ascii_case("POINTS.CUP")
+        || path
+            .split('/')
+            .any(|component| matches!(component, "" | "." | ".."));
+    if invalid {
+        return Err(Error::InvalidFilename(path.to_string()));
+    }
+    Ok(())
+}
+
+/// Enables ZIP64 extensions for an entry of the given size if it doesn't fit
+/// into the 32-bit size fields of the classic ZIP format.
+///
+/// `ZipWriter` refuses to write more than 4 GiB into an entry without them,
+/// and the flag can't be changed once the entry has been started.
+pub(crate) fn sized_file_options(
+    options: zip::write::FileOptions<'_, ()>,
+    size: u64,
+) -> zip::write::FileOptions<'_, ()> {
+    if size >= u64::from(u32::MAX) {
+        options.large_file(true)
+    } else {
+        options
+    }
+}
+
+/// Writes the given pictures into the `pics/` directory of the pics archive,
+/// using `prefix` as the directory name.
+///
+/// Source CUPX files of [`PictureSource::CupxEntry`] pictures are opened once
+/// and kept open until all pictures are written.
+///
+/// With `auto_compression`, the first bytes of every picture are read before
+/// its entry is started, so that already compressed formats can be stored
+/// instead of deflated.
+///
+/// If `checksums` is given, the SHA-256 digest of every picture is pushed to
+/// it while the picture is written.
+fn write_pictures<W: Write + Seek>(
+    pics_zip: &mut zip::ZipWriter<W>,
+    pictures: Vec<(&str, &PictureSource<'_>)>,
+    prefix: &str,
+    options: zip::write::FileOptions<()>,
+    auto_compression: bool,
+    mut checksums: Option<&mut Vec<(String, [u8; 32])>>,
+) -> Result<(), Error> {
+    #[cfg(not(feature = "sha2"))]
+    let _ = checksums.take();
+
+    #[cfg(feature = "std")]
+    let mut sources = HashMap::new();
+
+    for (filename, source) in pictures {
+        let zip_filename = format!("{prefix}{filename}");
+        #[cfg(feature = "sha2")]
+        let mut hasher = Sha256::new();
+
+        let (size, mut reader): (u64, Box<dyn Read + '_>) = match source {
+            PictureSource::Bytes(data) => (data.len() as u64, Box::new(*data)),
+            #[cfg(feature = "std")]
+            PictureSource::Path(path) => {
+                let file = File::open(path)?;
+                (file.metadata()?.len(), Box::new(file))
+            }
+            #[cfg(feature = "std")]
+            PictureSource::File(file) => {
+                let mut file = *file;
+                file.rewind()?;
+                (file.metadata()?.len(), Box::new(file))
+            }
+            #[cfg(feature = "std")]
+            PictureSource::CupxEntry { path, name } => {
+                let source = match sources.entry(*path) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let (source, _) = CupxFile::from_reader_pics_only(File::open(path)?)?;
+                        entry.insert(source)
+                    }
+                };
+                let size = source.picture(name).map_or(0, |info| info.size);
+                (size, Box::new(source.read_picture(name)?))
+            }
+        };
+
+        #[cfg(feature = "sha2")]
+        if checksums.is_some() {
+            reader = Box::new(HashingReader::new(reader, &mut hasher));
+        }
+
+        let mut options = sized_file_options(options, size);
+        let mut header = Vec::with_capacity(PictureFormat::HEADER_LEN);
+        if auto_compression {
+            (&mut reader)
+                .take(PictureFormat::HEADER_LEN as u64)
+                .read_to_end(&mut header)?;
+            let compressed =
+                PictureFormat::detect(&header).is_some_and(PictureFormat::is_compressed);
+            options = options.compression_method(if compressed {
+                CompressionMethod::Stored
+            } else {
+                CompressionMethod::Deflated
+            });
+        }
+
+        pics_zip.start_file(&zip_filename, options)?;
+        pics_zip.write_all(&header)?;
+        std::io::copy(&mut reader, pics_zip)?;
+        drop(reader);
+
+        #[cfg(feature = "sha2")]
+        if let Some(checksums) = checksums.as_mut() {
+            checksums.push((filename.to_string(), hasher.finalize().into()));
+        }
+    }
+
+    Ok(())
+}
+
+/// The content of `POINTS.CUP`.
+#[derive(Clone, Copy)]
+pub(crate) enum PointsCup<'a> {
+    /// CUP data that is serialized while writing.
+    Parsed(&'a CupFile),
+    /// Pre-serialized CUP data that is written verbatim.
+    Raw(&'a [u8]),
+}
+
+/// Writes the points archive containing `POINTS.CUP` to the given writer.
+///
+/// Any supplementary CUP files in `extra_files` and the checksum `manifest`
+/// are written after `POINTS.CUP`.
+///
+/// The archive is built in memory first and then appended to `writer` in one go,
+/// so that the points archive can follow the pics archive in the same stream.
+pub(crate) fn write_points_archive<W: Write>(
+    writer: &mut W,
+    points_cup: PointsCup<'_>,
+    extra_files: &[(&str, &CupFile)],
+    manifest: Option<&str>,
+    options: zip::write::FileOptions<()>,
+) -> Result<(), Error> {
+    let mut points_buffer = Vec::new();
+    let mut points_zip = zip::ZipWriter::new(Cursor::new(&mut points_buffer));
+    write_points_files(&mut points_zip, points_cup, extra_files, manifest, options)?;
+    points_zip.finish()?;
+    writer.write_all(&points_buffer)?;
+    Ok(())
+}
+
+/// Serializes `cup_file` and writes it into the given archive as `filename`.
+///
+/// The CUP data is serialized up front, so that ZIP64 extensions can be
+/// enabled based on its size.
+fn write_cup_file<W: Write + Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    filename: &str,
+    cup_file: &CupFile,
+    options: zip::write::FileOptions<()>,
+) -> Result<(), Error> {
+    let mut data = Vec::new();
+    cup_file.to_writer(&mut data)?;
+    zip.start_file(filename, sized_file_options(options, data.len() as u64))?;
+    zip.write_all(&data)?;
+    Ok(())
+}
+
+/// Writes `POINTS.CUP`, any supplementary CUP files and the checksum manifest
+/// into the given archive.
+fn write_points_files<W: Write + Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    points_cup: PointsCup<'_>,
+    extra_files: &[(&str, &CupFile)],
+    manifest: Option<&str>,
+    options: zip::write::FileOptions<()>,
+) -> Result<(), Error> {
+    match points_cup {
+        PointsCup::Parsed(cup_file) => write_cup_file(zip, "POINTS.CUP", cup_file, options)?,
+        PointsCup::Raw(bytes) => {
+            zip.start_file(
+                "POINTS.CUP",
+                sized_file_options(options, bytes.len() as u64),
+            )?;
+            zip.write_all(bytes)?;
+        }
+    }
+    for (filename, cup_file) in extra_files {
+        write_cup_file(zip, filename, cup_file, options)?;
+    }
+    if let Some(manifest) = manifest {
+        zip.start_file(CHECKSUMS_FILENAME, options)?;
+        zip.write_all(manifest.as_bytes())?;
+    }
+    Ok(())
 }
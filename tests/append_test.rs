@@ -0,0 +1,50 @@
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxLayout, CupxWriter, Error, Warning};
+use std::io::{Cursor, Read};
+
+fn read(cupx: &mut CupxFile<Cursor<&Vec<u8>>>, name: &str) -> Vec<u8> {
+    let mut data = Vec::new();
+    cupx.read_picture(name)
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    data
+}
+
+#[test]
+fn test_append_pictures() {
+    for layout in [CupxLayout::Concatenated, CupxLayout::SingleZip] {
+        let cup_file = CupFile::default();
+        let buffer = CupxWriter::new(&cup_file)
+            .add_picture("a.jpg", &b"a"[..])
+            .add_picture("b.jpg", &b"b"[..])
+            .layout(layout)
+            .write_to_vec()
+            .unwrap();
+        let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+
+        let mut output = Vec::new();
+        cupx.append_pictures(
+            &[("B.jpg", &b"new b"[..]), ("c.jpg", &b"c"[..])],
+            Cursor::new(&mut output),
+        )
+        .unwrap();
+
+        let (mut result, warnings) = CupxFile::from_reader(Cursor::new(&output)).unwrap();
+        assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+        assert_eq!(
+            result.picture_names().collect::<Vec<_>>(),
+            vec!["a.jpg", "B.jpg", "c.jpg"]
+        );
+        assert_eq!(read(&mut result, "a.jpg"), b"a");
+        assert_eq!(read(&mut result, "b.jpg"), b"new b");
+        assert_eq!(read(&mut result, "c.jpg"), b"c");
+    }
+}
+
+#[test]
+fn test_append_pictures_invalid_filename() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let result = cupx.append_pictures(&[("pics/a.jpg", &b"a"[..])], Cursor::new(Vec::new()));
+    assert!(matches!(result, Err(Error::InvalidFilename(_))));
+}
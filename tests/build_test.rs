@@ -0,0 +1,37 @@
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, Warning, build};
+
+#[test]
+fn test_build() {
+    let dir = std::env::temp_dir().join("test_cupx_build");
+    let _ = std::fs::remove_dir_all(&dir);
+    let pics_dir = dir.join("pics");
+    std::fs::create_dir_all(pics_dir.join("subdir")).unwrap();
+    std::fs::write(pics_dir.join("b.jpg"), b"b").unwrap();
+    std::fs::write(pics_dir.join("a.jpg"), b"a").unwrap();
+    std::fs::write(pics_dir.join(".DS_Store"), b"junk").unwrap();
+    std::fs::write(pics_dir.join("subdir/c.jpg"), b"c").unwrap();
+
+    let cup_file = CupFile::default();
+    let out = dir.join("out.cupx");
+    build(&cup_file, Some(&pics_dir), &out).unwrap();
+
+    let (mut cupx, warnings) = CupxFile::from_path(&out).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+    assert_eq!(
+        cupx.picture_names().collect::<Vec<_>>(),
+        vec!["a.jpg", "b.jpg"]
+    );
+    let mut picture = Vec::new();
+    std::io::Read::read_to_end(&mut cupx.read_picture("b.jpg").unwrap(), &mut picture).unwrap();
+    assert_eq!(picture, b"b");
+
+    build(&cup_file, None, &out).unwrap();
+    let (cupx, _) = CupxFile::from_path(&out).unwrap();
+    assert_eq!(cupx.picture_names().count(), 0);
+
+    let result = build(&cup_file, Some(&dir.join("missing")), &out);
+    assert!(matches!(result, Err(seeyou_cupx::Error::Io(_))));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
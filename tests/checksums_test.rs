@@ -0,0 +1,80 @@
+#![cfg(feature = "sha2")]
+
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{ChecksumResult, CupxFile, CupxLayout, CupxWriter};
+
+const MANIFEST: &str = "\
+ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  abc.jpg
+e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  empty.jpg
+";
+
+#[test]
+fn test_write_checksums() {
+    let cup_file = CupFile::default();
+    for layout in [CupxLayout::Concatenated, CupxLayout::SingleZip] {
+        let buffer = CupxWriter::new(&cup_file)
+            .add_picture("abc.jpg", &b"abc"[..])
+            .add_picture("empty.jpg", &b""[..])
+            .layout(layout)
+            .auto_compression(true)
+            .write_checksums(true)
+            .write_to_vec()
+            .unwrap();
+
+        let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+        let manifest = cupx.read_text_entry("CHECKSUMS.txt").unwrap().unwrap();
+        assert_eq!(manifest, MANIFEST);
+        assert_eq!(
+            cupx.verify_checksums().unwrap(),
+            vec![
+                ChecksumResult::Match("abc.jpg".to_string()),
+                ChecksumResult::Match("empty.jpg".to_string()),
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_verify_checksums_tampered() {
+    let cup_file = CupFile::default();
+    let original = CupxWriter::new(&cup_file)
+        .add_picture("abc.jpg", &b"abc"[..])
+        .add_picture("empty.jpg", &b""[..])
+        .write_checksums(true)
+        .write_to_vec()
+        .unwrap();
+    let tampered = CupxWriter::new(&cup_file)
+        .add_picture("abc.jpg", &b"abd"[..])
+        .add_picture("new.jpg", &b"new"[..])
+        .write_to_vec()
+        .unwrap();
+
+    // Combine the pics archive of the tampered file with the points archive
+    // of the original, which contains the manifest
+    let (original_cupx, _) = CupxFile::from_bytes(&original).unwrap();
+    let (tampered_cupx, _) = CupxFile::from_bytes(&tampered).unwrap();
+    let mut buffer = tampered[..tampered_cupx.points_start() as usize].to_vec();
+    buffer.extend_from_slice(&original[original_cupx.points_start() as usize..]);
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(
+        cupx.verify_checksums().unwrap(),
+        vec![
+            ChecksumResult::Mismatch("abc.jpg".to_string()),
+            ChecksumResult::Missing("empty.jpg".to_string()),
+            ChecksumResult::Unlisted("new.jpg".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_verify_checksums_without_manifest() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("abc.jpg", &b"abc"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(cupx.verify_checksums().unwrap(), vec![]);
+}
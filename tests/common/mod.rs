@@ -0,0 +1,22 @@
+use seeyou_cupx::cup::{Elevation, Waypoint, WaypointStyle};
+
+/// Returns a waypoint with the given name and pictures, and defaults for all
+/// other fields.
+pub fn waypoint(name: &str, pictures: &[&str]) -> Waypoint {
+    Waypoint {
+        name: name.to_string(),
+        code: String::new(),
+        country: String::new(),
+        latitude: 45.0,
+        longitude: 7.0,
+        elevation: Elevation::Meters(0.0),
+        style: WaypointStyle::Waypoint,
+        runway_direction: None,
+        runway_length: None,
+        runway_width: None,
+        frequency: String::new(),
+        description: String::new(),
+        userdata: String::new(),
+        pictures: pictures.iter().map(|picture| picture.to_string()).collect(),
+    }
+}
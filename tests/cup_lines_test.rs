@@ -0,0 +1,75 @@
+use seeyou_cupx::cup::{CupFile, Encoding};
+use seeyou_cupx::{CupxFile, CupxLayout, CupxWriter, ParseOptions};
+use std::io::Cursor;
+
+const HEADER: &str = "name,code,country,lat,lon,elev,style";
+
+#[test]
+fn test_cup_lines() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let lines = cupx
+        .cup_lines()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(lines[0], cupx.cup_header());
+    assert!(lines.len() > cupx.waypoints().len());
+
+    // The iterator doesn't borrow the file
+    let mut lines = cupx.cup_lines().unwrap();
+    let first_waypoint = &cupx.waypoints()[0].name;
+    assert!(
+        lines
+            .nth(1)
+            .unwrap()
+            .unwrap()
+            .contains(first_waypoint.as_str())
+    );
+}
+
+#[test]
+fn test_cup_lines_windows_1252() {
+    let mut points = format!("{HEADER}\r\n").into_bytes();
+    points.extend_from_slice(b"\"M\xfcnchen\",,DE,4808.000N,01134.000E,520m,1\r\n");
+    let buffer = CupxWriter::new(&CupFile::default())
+        .set_raw_points(&points)
+        .write_to_vec()
+        .unwrap();
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(cupx.waypoints()[0].name, "München");
+    let lines = cupx
+        .cup_lines()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        lines,
+        vec![HEADER, "\"München\",,DE,4808.000N,01134.000E,520m,1"]
+    );
+
+    // A configured encoding takes precedence over the detection
+    let options = ParseOptions::new().encoding(Encoding::Utf8);
+    let (mut cupx, _) = CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    let line = cupx.cup_lines().unwrap().nth(1).unwrap().unwrap();
+    assert!(line.starts_with("\"M\u{fffd}nchen\""));
+}
+
+#[test]
+fn test_cup_lines_single_zip() {
+    let points = format!("\u{feff}{HEADER}\nA,,,4500.000N,00700.000E,0m,1");
+    let buffer = CupxWriter::new(&CupFile::default())
+        .set_raw_points(points.as_bytes())
+        .add_picture("a.jpg", &b"a"[..])
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    let lines = cupx
+        .cup_lines()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(lines, vec![HEADER, "A,,,4500.000N,00700.000E,0m,1"]);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::waypoint;
+use insta::assert_debug_snapshot;
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxWriter};
+use std::io::Cursor;
+
+#[test]
+fn test_diff_identical() {
+    let (mut a, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (mut b, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert!(a.diff(&mut b).unwrap().is_empty());
+}
+
+#[test]
+fn test_diff() {
+    let (old, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+
+    let old_cup = CupFile {
+        waypoints: old.waypoints()[..3].to_vec(),
+        ..Default::default()
+    };
+
+    let mut new_cup = CupFile {
+        waypoints: old.waypoints()[1..4].to_vec(),
+        ..Default::default()
+    };
+    new_cup.waypoints[0].description = "Changed".to_string();
+
+    let old_buffer = CupxWriter::new(&old_cup)
+        .add_picture("removed.jpg", &b"removed"[..])
+        .add_picture("changed.jpg", &b"before"[..])
+        .add_picture("same.jpg", &b"same"[..])
+        .write_to_vec()
+        .unwrap();
+    let new_buffer = CupxWriter::new(&new_cup)
+        .add_picture("changed.jpg", &b"after"[..])
+        .add_picture("same.jpg", &b"same"[..])
+        .add_picture("added.jpg", &b"added"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let (mut a, _) = CupxFile::from_reader(Cursor::new(&old_buffer)).unwrap();
+    let (mut b, _) = CupxFile::from_reader(Cursor::new(&new_buffer)).unwrap();
+
+    let mut diff = a.diff(&mut b).unwrap();
+    diff.added_pictures.sort();
+    diff.removed_pictures.sort();
+    assert_debug_snapshot!(diff, @r#"
+    CupxDiff {
+        added_waypoints: [
+            "Andon - Cipieres (Kat B 2024)",
+        ],
+        removed_waypoints: [
+            "Aiton (Kat A 2022)",
+        ],
+        changed_waypoints: [
+            "Aix En Diois (Kat B 2023)",
+        ],
+        added_tasks: [],
+        removed_tasks: [],
+        changed_tasks: [],
+        added_pictures: [
+            "added.jpg",
+        ],
+        removed_pictures: [
+            "removed.jpg",
+        ],
+        changed_pictures: [
+            "changed.jpg",
+        ],
+    }
+    "#);
+}
+
+#[test]
+fn test_diff_duplicate_names() {
+    let mut old_cup = CupFile::default();
+    old_cup.waypoints.push(waypoint("A", &[]));
+    old_cup.waypoints.push(waypoint("A", &["a.jpg"]));
+    old_cup.waypoints.push(waypoint("B", &[]));
+    old_cup.waypoints.push(waypoint("B", &[]));
+
+    let mut new_cup = CupFile::default();
+    new_cup.waypoints.push(waypoint("A", &[]));
+    new_cup.waypoints.push(waypoint("A", &[]));
+    new_cup.waypoints.push(waypoint("A", &[]));
+    new_cup.waypoints.push(waypoint("B", &[]));
+
+    let old_buffer = CupxWriter::new(&old_cup).write_to_vec().unwrap();
+    let new_buffer = CupxWriter::new(&new_cup).write_to_vec().unwrap();
+    let (mut a, _) = CupxFile::from_reader(Cursor::new(&old_buffer)).unwrap();
+    let (mut b, _) = CupxFile::from_reader(Cursor::new(&new_buffer)).unwrap();
+
+    let diff = a.diff(&mut b).unwrap();
+    assert_eq!(diff.added_waypoints, vec!["A"]);
+    assert_eq!(diff.removed_waypoints, vec!["B"]);
+    assert_eq!(diff.changed_waypoints, vec!["A"]);
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "sha2")]
+
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxWriter};
+
+fn hex(digest: &[u8; 32]) -> String {
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn test_picture_digest() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("abc.jpg", &b"abc"[..])
+        .add_picture("empty.jpg", &b""[..])
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+    assert_eq!(
+        hex(&cupx.picture_digest("ABC.jpg").unwrap()),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+
+    let digests = cupx.all_picture_digests().unwrap();
+    assert_eq!(digests.len(), 2);
+    assert_eq!(
+        hex(&digests["empty.jpg"]),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+}
+
+#[test]
+fn test_picture_digest_missing() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert!(cupx.picture_digest("missing.jpg").is_err());
+}
+
+#[test]
+fn test_duplicate_pictures() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"same"[..])
+        .add_picture("b.jpg", &b"other"[..])
+        .add_picture("c.jpg", &b"same"[..])
+        .add_picture("d.jpg", &b"other"[..])
+        .add_picture("e.jpg", &b"unique"[..])
+        .add_picture("f.jpg", &b"same"[..])
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+    assert_eq!(
+        cupx.duplicate_pictures().unwrap(),
+        vec![vec!["a.jpg", "c.jpg", "f.jpg"], vec!["b.jpg", "d.jpg"]]
+    );
+
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert_eq!(
+        cupx.duplicate_pictures().unwrap(),
+        Vec::<Vec<String>>::new()
+    );
+}
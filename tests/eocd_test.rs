@@ -0,0 +1,42 @@
+use insta::assert_compact_debug_snapshot;
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxWriter, Eocd, parse_eocd};
+
+#[test]
+fn test_parse_eocd() {
+    let buffer = CupxWriter::new(&CupFile::default())
+        .add_picture("a.jpg", &b"a"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let offsets = buffer
+        .windows(4)
+        .enumerate()
+        .filter(|(_, window)| window == Eocd::SIGNATURE)
+        .map(|(offset, _)| offset)
+        .collect::<Vec<_>>();
+    assert_eq!(offsets.len(), 2);
+
+    let pics = parse_eocd(&buffer[offsets[0]..]).unwrap();
+    assert_compact_debug_snapshot!(pics, @"Eocd { entry_count: 1, central_directory_size: 56, central_directory_offset: 43, comment_length: 0 }");
+
+    let points = parse_eocd(&buffer[offsets[1]..]).unwrap();
+    assert_eq!(
+        offsets[1] as u64 + points.record_size(),
+        buffer.len() as u64
+    );
+}
+
+#[test]
+fn test_parse_eocd_invalid() {
+    let mut record = [0; Eocd::MIN_SIZE];
+    assert_eq!(parse_eocd(&record), None);
+
+    record[..4].copy_from_slice(Eocd::SIGNATURE);
+    assert_eq!(parse_eocd(&record[..Eocd::MIN_SIZE - 1]), None);
+
+    record[20..].copy_from_slice(&300u16.to_le_bytes());
+    let eocd = parse_eocd(&record).unwrap();
+    assert_eq!(eocd.comment_length, 300);
+    assert_eq!(eocd.record_size(), 322);
+}
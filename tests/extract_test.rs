@@ -0,0 +1,75 @@
+mod common;
+
+use common::waypoint;
+use seeyou_cupx::CupxFile;
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxLayout, CupxWriter};
+
+#[test]
+fn test_extract_pictures_by_waypoint() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint(
+        "Airport A/B",
+        &["shared.jpg", "a.jpg", "missing.jpg"],
+    ));
+    cup_file.waypoints.push(waypoint("Field", &["SHARED.jpg"]));
+    cup_file.waypoints.push(waypoint("Empty", &[]));
+
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("shared.jpg", &b"shared"[..])
+        .add_picture("a.jpg", &b"a"[..])
+        .add_picture("extra.jpg", &b"extra"[..])
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+    let dir = std::env::temp_dir().join("test_cupx_extract_by_waypoint");
+    let _ = std::fs::remove_dir_all(&dir);
+    cupx.extract_pictures_by_waypoint(&dir).unwrap();
+
+    let read = |path: &str| std::fs::read(dir.join(path)).unwrap();
+    assert_eq!(read("Airport A_B/shared.jpg"), b"shared");
+    assert_eq!(read("Airport A_B/a.jpg"), b"a");
+    assert_eq!(read("Field/shared.jpg"), b"shared");
+    assert_eq!(read("_unreferenced/extra.jpg"), b"extra");
+    assert!(!dir.join("Empty").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_explode() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("Field", &["a.jpg"]));
+    let outlandings = CupFile::default();
+
+    for layout in [CupxLayout::Concatenated, CupxLayout::SingleZip] {
+        let mut writer = CupxWriter::new(&cup_file);
+        writer
+            .layout(layout)
+            .add_picture("a.jpg", &b"a"[..])
+            .add_picture("b.png", &b"b"[..])
+            .add_points_file("OUTLANDINGS.cup", &outlandings)
+            .unwrap();
+        let buffer = writer.write_to_vec().unwrap();
+        let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("test_cupx_explode_{layout:?}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        cupx.explode(&dir).unwrap();
+
+        let mut entries = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(entries, vec!["OUTLANDINGS.cup", "POINTS.CUP", "pics"]);
+
+        let (exploded, _) = CupFile::from_path(dir.join("POINTS.CUP")).unwrap();
+        assert_eq!(exploded.waypoints, cup_file.waypoints);
+        assert_eq!(std::fs::read(dir.join("pics/a.jpg")).unwrap(), b"a");
+        assert_eq!(std::fs::read(dir.join("pics/b.png")).unwrap(), b"b");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
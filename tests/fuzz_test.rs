@@ -0,0 +1,30 @@
+#![cfg(feature = "fuzz")]
+
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxWriter};
+
+#[test]
+fn test_try_parse() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert!(CupxFile::try_parse(&data).is_ok());
+    assert!(CupxFile::try_parse(b"").is_err());
+    assert!(CupxFile::try_parse(b"PK\x05\x06").is_err());
+}
+
+#[test]
+fn test_try_parse_corrupt_input() {
+    let data = CupxWriter::new(&CupFile::default())
+        .add_picture("photo.jpg", &b"\xFF\xD8\xFF\xE0 picture"[..])
+        .write_to_vec()
+        .unwrap();
+
+    // Must not panic, but may return an error
+    for len in 0..data.len() {
+        let _ = CupxFile::try_parse(&data[..len]);
+    }
+    for index in 0..data.len() {
+        let mut corrupt = data.clone();
+        corrupt[index] ^= 0xFF;
+        let _ = CupxFile::try_parse(&corrupt);
+    }
+}
@@ -0,0 +1,28 @@
+#![cfg(feature = "flate2")]
+
+use insta::assert_compact_debug_snapshot;
+use seeyou_cupx::CupxFile;
+use std::io::Write;
+
+#[test]
+fn test_from_gz_reader() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&data).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let (mut cupx, warnings) = CupxFile::from_gz_reader(&compressed[..]).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(cupx.waypoints().len(), 126);
+    assert!(cupx.read_picture("2_1034.jpg").is_ok());
+}
+
+#[test]
+fn test_from_gz_reader_not_gzip() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let result = CupxFile::from_gz_reader(&data[..]).map(|_| ());
+    assert_compact_debug_snapshot!(result, @"Err(NotGzip)");
+
+    let result = CupxFile::from_gz_reader(&[0x1f][..]).map(|_| ());
+    assert_compact_debug_snapshot!(result, @"Err(NotGzip)");
+}
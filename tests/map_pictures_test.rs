@@ -0,0 +1,59 @@
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxLayout, CupxWriter, Error, Warning};
+use std::io::{Cursor, Read};
+
+#[test]
+fn test_map_pictures() {
+    for layout in [CupxLayout::Concatenated, CupxLayout::SingleZip] {
+        let cup_file = CupFile::default();
+        let buffer = CupxWriter::new(&cup_file)
+            .add_picture("a.jpg", &b"a"[..])
+            .add_picture("drop.jpg", &b"drop"[..])
+            .add_picture("c.png", &b"c"[..])
+            .layout(layout)
+            .write_to_vec()
+            .unwrap();
+        let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+
+        let mut seen = Vec::new();
+        let mut output = Vec::new();
+        cupx.map_pictures(
+            |name, data| {
+                seen.push(name.to_string());
+                Ok(match name {
+                    "drop.jpg" => Vec::new(),
+                    _ => [&data[..], b"!"].concat(),
+                })
+            },
+            Cursor::new(&mut output),
+        )
+        .unwrap();
+        assert_eq!(seen, vec!["a.jpg", "drop.jpg", "c.png"]);
+
+        let (mut result, warnings) = CupxFile::from_reader(Cursor::new(&output)).unwrap();
+        assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+        assert_eq!(result.waypoints(), cupx.waypoints());
+        assert_eq!(
+            result.picture_names().collect::<Vec<_>>(),
+            vec!["a.jpg", "c.png"]
+        );
+
+        let mut data = Vec::new();
+        result
+            .read_picture("a.jpg")
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, b"a!");
+    }
+}
+
+#[test]
+fn test_map_pictures_error() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let result = cupx.map_pictures(
+        |name, _| Err(Error::InvalidFilename(name.to_string())),
+        Cursor::new(Vec::new()),
+    );
+    assert!(matches!(result, Err(Error::InvalidFilename(_))));
+}
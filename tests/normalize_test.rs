@@ -0,0 +1,74 @@
+mod common;
+
+use common::waypoint;
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxLayout, CupxWriter, ParseOptions};
+use std::io::{Cursor, Read};
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+const JPEG: &[u8] = b"\xFF\xD8\xFF\xE0\0\x10JFIF";
+
+fn write(layout: CupxLayout) -> Vec<u8> {
+    let mut cup_file = CupFile::default();
+    cup_file
+        .waypoints
+        .push(waypoint("A", &["Renamed.JPG", "photo.jpg", "missing.jpg"]));
+    let extra = CupFile::default();
+
+    CupxWriter::new(&cup_file)
+        .add_picture("renamed.jpg", PNG)
+        .add_picture("photo.jpg", JPEG)
+        .add_picture("taken.jpg", PNG)
+        .add_picture("taken.png", PNG)
+        .add_points_file("EXTRA.CUP", &extra)
+        .unwrap()
+        .layout(layout)
+        .write_to_vec()
+        .unwrap()
+}
+
+#[test]
+fn test_normalize_picture_extensions() {
+    for layout in [CupxLayout::Concatenated, CupxLayout::SingleZip] {
+        let buffer = write(layout);
+        let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+        let mut output = Vec::new();
+        let renames = cupx
+            .normalize_picture_extensions(Cursor::new(&mut output))
+            .unwrap();
+        assert_eq!(
+            renames,
+            vec![("renamed.jpg".to_string(), "renamed.png".to_string())]
+        );
+
+        let options = ParseOptions::new().eager_validate(true);
+        let (mut result, warnings) =
+            CupxFile::from_reader_with_options(Cursor::new(&output), options).unwrap();
+        assert_eq!(
+            result.waypoints()[0].pictures,
+            vec!["renamed.png", "photo.jpg", "missing.jpg"]
+        );
+        assert_eq!(
+            result.picture_names().collect::<Vec<_>>(),
+            vec!["renamed.png", "photo.jpg", "taken.jpg", "taken.png"]
+        );
+
+        // `taken.jpg` can't be renamed, since `taken.png` already exists
+        assert_eq!(warnings.len(), 1);
+
+        let mut picture = Vec::new();
+        result
+            .read_picture("renamed.png")
+            .unwrap()
+            .read_to_end(&mut picture)
+            .unwrap();
+        assert_eq!(picture, PNG);
+
+        let mut pics = Vec::new();
+        let mut points = Vec::new();
+        result.split(&mut pics, &mut points).unwrap();
+        let points = zip::ZipArchive::new(Cursor::new(points)).unwrap();
+        assert!(points.file_names().any(|name| name == "EXTRA.CUP"));
+    }
+}
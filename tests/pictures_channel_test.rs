@@ -0,0 +1,59 @@
+#![cfg(feature = "parallel")]
+
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxWriter, Error, ParseOptions};
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+
+#[test]
+fn test_pictures_channel() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let reader = Cursor::new(Arc::<[u8]>::from(data));
+    let (mut cupx, _) = CupxFile::from_reader(reader).unwrap();
+
+    let mut expected = cupx.picture_names().collect::<Vec<_>>();
+    expected.sort();
+
+    for workers in [0, 1, 3, 64] {
+        let mut pictures = cupx
+            .pictures_channel(workers)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        pictures.sort();
+
+        let names = pictures.iter().map(|(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(names, expected.iter().collect::<Vec<_>>());
+    }
+
+    let (name, picture) = cupx
+        .pictures_channel(2)
+        .into_iter()
+        .map(Result::unwrap)
+        .find(|(name, _)| name == "2_1034.jpg")
+        .unwrap();
+    let mut expected = Vec::new();
+    cupx.read_picture(&name)
+        .unwrap()
+        .read_to_end(&mut expected)
+        .unwrap();
+    assert_eq!(picture, expected);
+}
+
+#[test]
+fn test_pictures_channel_errors() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("small.jpg", &[0; 10][..])
+        .add_picture("large.jpg", &[0; 100][..])
+        .write_to_vec()
+        .unwrap();
+
+    let options = ParseOptions::new().max_picture_size(50);
+    let (cupx, _) = CupxFile::from_reader_with_options(Cursor::new(buffer), options).unwrap();
+
+    let results = cupx.pictures_channel(1).into_iter().collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert!(matches!(&results[0], Ok((name, data)) if name == "small.jpg" && data.len() == 10));
+    assert!(matches!(results[1], Err(Error::PictureTooLarge { .. })));
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::waypoint;
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxLayout, CupxWriter};
+use std::io::Cursor;
+
+#[test]
+fn test_prune_unreferenced() {
+    let cup_file = cup_file_with_pictures(&["a.jpg"]);
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"a"[..])
+        .add_picture("b.jpg", &[0u8; 1000][..])
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+
+    let mut output = Vec::new();
+    let stats = cupx.prune_unreferenced(Cursor::new(&mut output)).unwrap();
+    assert_eq!(stats.pictures_removed, 1);
+    assert_eq!(stats.bytes_removed, (buffer.len() - output.len()) as u64);
+    assert!(stats.bytes_removed > 0);
+
+    let (result, warnings) = CupxFile::from_reader(Cursor::new(&output)).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(result.waypoints(), cupx.waypoints());
+    assert_eq!(result.picture_names().collect::<Vec<_>>(), vec!["a.jpg"]);
+}
+
+#[test]
+fn test_prune_unreferenced_single_zip() {
+    let cup_file = cup_file_with_pictures(&["a.jpg"]);
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"a"[..])
+        .add_picture("b.jpg", &b"b"[..])
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+
+    let mut output = Vec::new();
+    let stats = cupx.prune_unreferenced(Cursor::new(&mut output)).unwrap();
+    assert_eq!(stats.pictures_removed, 1);
+
+    let (result, warnings) = CupxFile::from_reader(Cursor::new(&output)).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(result.waypoints().len(), 1);
+    assert_eq!(result.picture_names().collect::<Vec<_>>(), vec!["a.jpg"]);
+}
+
+#[test]
+fn test_prune_unreferenced_no_pictures_archive() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+
+    let mut output = Vec::new();
+    let stats = cupx.prune_unreferenced(Cursor::new(&mut output)).unwrap();
+    assert_eq!(stats.pictures_removed, 0);
+
+    let (result, _) = CupxFile::from_reader(Cursor::new(&output)).unwrap();
+    assert_eq!(result.waypoints().len(), 221);
+}
+
+fn cup_file_with_pictures(pictures: &[&str]) -> CupFile {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("Field", pictures));
+    cup_file
+}
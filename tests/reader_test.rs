@@ -1,6 +1,14 @@
 use insta::assert_compact_debug_snapshot;
-use seeyou_cupx::CupxFile;
-use std::io::Read;
+use seeyou_cupx::cup::{
+    CupFile, Distance, Elevation, Encoding, ObsZoneStyle, ObservationZone, Task, Waypoint,
+    WaypointStyle,
+};
+use seeyou_cupx::{
+    BoundingBox, CupxFile, CupxLayout, CupxWriter, DEFAULT_MAX_WARNINGS, ParseOptions,
+    PictureFormat, TaskSummary, Warning,
+};
+use std::borrow::Cow;
+use std::io::{Cursor, Read};
 
 #[test]
 fn test_westalpen() {
@@ -27,3 +35,1479 @@ fn test_ec25_no_pictures_zip() {
     assert_compact_debug_snapshot!(warnings, @"[NoPicturesArchive]");
     assert_eq!(cupx.picture_names().count(), 0);
 }
+
+#[test]
+fn test_open_with_warning_callback() {
+    let mut warnings = Vec::new();
+    let cupx = CupxFile::open("tests/fixtures/EC25_no_pictures_zip.cupx", |warning| {
+        warnings.push(warning.clone())
+    })
+    .unwrap();
+    assert_eq!(cupx.waypoints().len(), 221);
+    assert_compact_debug_snapshot!(warnings, @"[NoPicturesArchive]");
+
+    let result = CupxFile::open("tests/fixtures/missing.cupx", |_| unreachable!());
+    assert!(result.is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_from_path_no_follow() {
+    let fixture = "tests/fixtures/EC25_no_pictures_zip.cupx";
+    let (cupx, _) = CupxFile::from_path_no_follow(fixture).unwrap();
+    assert_eq!(cupx.waypoints().len(), 221);
+
+    let dir = std::env::temp_dir().join("test_cupx_no_follow");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let link = dir.join("link.cupx");
+    std::os::unix::fs::symlink(std::fs::canonicalize(fixture).unwrap(), &link).unwrap();
+
+    assert!(CupxFile::from_path(&link).is_ok());
+    let result = CupxFile::from_path_no_follow(&link);
+    assert!(matches!(result, Err(seeyou_cupx::Error::Io(_))));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_all_waypoint_pictures() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let pictures = cupx.all_waypoint_pictures().unwrap();
+    assert_eq!(pictures.len(), 126);
+
+    let aix = &pictures["Aix En Diois (Kat B 2023)"];
+    assert_eq!(aix.len(), 2);
+
+    let mut expected = Vec::new();
+    cupx.read_picture("2_2435.jpg")
+        .unwrap()
+        .read_to_end(&mut expected)
+        .unwrap();
+    assert_eq!(aix[1], expected);
+}
+
+#[test]
+fn test_from_bytes() {
+    let data = std::fs::read("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    let (cupx, warnings) = CupxFile::from_bytes(&data).unwrap();
+    assert_eq!(cupx.waypoints().len(), 221);
+    assert_compact_debug_snapshot!(warnings, @"[NoPicturesArchive]");
+
+    let cupx = CupxFile::try_from(&data[..]).unwrap();
+    assert_eq!(cupx.waypoints().len(), 221);
+}
+
+#[test]
+fn test_try_from_invalid_bytes() {
+    let result = CupxFile::try_from(&b"not a cupx file"[..]);
+    assert_compact_debug_snapshot!(result.err(), @"Some(InvalidCupx)");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_try_from_file_and_path() {
+    let path = std::path::PathBuf::from("tests/fixtures/EC25_no_pictures_zip.cupx");
+    let cupx = CupxFile::try_from(std::fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(cupx.waypoints().len(), 221);
+
+    let cupx = CupxFile::try_from(path).unwrap();
+    assert_eq!(cupx.waypoints().len(), 221);
+
+    let result = CupxFile::try_from(std::path::PathBuf::from("tests/fixtures/missing.cupx"));
+    assert!(matches!(result, Err(seeyou_cupx::Error::Io(_))));
+}
+
+#[test]
+fn test_mutable_access() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (mut cupx, _) = CupxFile::from_bytes(&data).unwrap();
+
+    cupx.waypoints_mut()
+        .retain(|waypoint| waypoint.country == "CH");
+    cupx.waypoints_mut()[0].name = "Renamed".to_string();
+    cupx.tasks_mut().clear();
+    assert_eq!(cupx.waypoints().len(), 6);
+    assert_eq!(cupx.waypoints()[0].name, "Renamed");
+
+    let buffer = CupxWriter::new(cupx.cup_file()).write_to_vec().unwrap();
+    let (written, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(written.waypoints(), cupx.waypoints());
+
+    // Deferred parsing happens before the first mutable access
+    let options = ParseOptions::new().lazy_cup(true);
+    let (mut cupx, _) = CupxFile::from_reader_with_options(Cursor::new(&data), options).unwrap();
+    cupx.cup_file_mut().waypoints.truncate(1);
+    assert_eq!(cupx.waypoints().len(), 1);
+}
+
+#[test]
+fn test_as_ref_cup_file() {
+    fn waypoint_count(cup_file: impl AsRef<CupFile>) -> usize {
+        cup_file.as_ref().waypoints.len()
+    }
+
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert_eq!(waypoint_count(&cupx), cupx.waypoints().len());
+    assert_eq!(waypoint_count(cupx), 126);
+}
+
+#[test]
+fn test_out_of_range_coordinates() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("Valid", 45.0, 7.0));
+    cup_file.waypoints.push(waypoint("Invalid", 95.0, 7.0));
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+
+    let (cupx, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(cupx.waypoints().len(), 1);
+    assert_compact_debug_snapshot!(warnings, @r#"[CupParseIssue { message: "Skipped waypoint: Latitude out of range: '95' (must be between -90 and 90)", line: Some(3) }]"#);
+}
+
+#[test]
+fn test_warning_categories() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("Invalid", 95.0, 7.0));
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("test.jpg", &b"data"[..])
+        .prefix_pictures(false)
+        .write_to_vec()
+        .unwrap();
+
+    let (_, warnings) = CupxFile::from_bytes(&buffer).unwrap();
+    let (cup_warnings, structural_warnings): (Vec<_>, Vec<_>) = warnings
+        .into_iter()
+        .partition(|warning| warning.is_cup_issue());
+    assert_compact_debug_snapshot!(cup_warnings, @r#"[CupParseIssue { message: "Skipped waypoint: Latitude out of range: '95' (must be between -90 and 90)", line: Some(2) }, EmptyWaypointData]"#);
+    assert_compact_debug_snapshot!(structural_warnings, @"[PicturesWithoutPrefix]");
+    assert!(structural_warnings.iter().all(Warning::is_structural));
+
+    let truncated = Warning::WarningsTruncated { total: 10 };
+    assert!(!truncated.is_cup_issue());
+    assert!(!truncated.is_structural());
+}
+
+#[test]
+fn test_suspicious_eocd_comment() {
+    let cup_file = CupFile::default();
+    let mut buffer = CupxWriter::new(&cup_file)
+        .add_picture("test.jpg", &b"data"[..])
+        .write_to_vec()
+        .unwrap();
+
+    // Corrupt the comment length of the pics archive EOCD record
+    let eocd_offset = buffer
+        .windows(4)
+        .position(|window| window == b"PK\x05\x06")
+        .unwrap();
+    buffer[eocd_offset + 20..eocd_offset + 22].copy_from_slice(&u16::MAX.to_le_bytes());
+
+    let (mut cupx, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[SuspiciousEocdComment, EmptyWaypointData]");
+    assert_eq!(cupx.waypoints().len(), 0);
+
+    // The `zip` crate refuses to open the pics archive with the corrupt comment
+    assert_eq!(cupx.picture_names().count(), 0);
+
+    // ... but the raw archives can still be separated
+    let mut pics = Vec::new();
+    let mut points = Vec::new();
+    assert!(cupx.split(&mut pics, &mut points).unwrap());
+    assert_eq!(pics.len(), eocd_offset + 22);
+}
+
+#[test]
+fn test_zip64_points_archive() {
+    // A ZIP64 comment that looks like another EOCD record
+    let mut points = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut points));
+        zip.set_raw_zip64_comment(Some(b"PK\x05\x06 not an EOCD record"[..].into()));
+        zip.start_file("POINTS.CUP", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, b"name,code,country,lat,lon,elev,style\n").unwrap();
+        zip.finish().unwrap();
+    }
+
+    let (cupx, warnings) = CupxFile::from_reader(Cursor::new(&points)).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[NoPicturesArchive, EmptyWaypointData]");
+    assert_eq!(cupx.archive_stats().points_entry_count, 1);
+
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        zip.start_file("pics/a.jpg", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, b"a").unwrap();
+        zip.finish().unwrap();
+    }
+    let pics_size = buffer.len() as u64;
+    buffer.extend_from_slice(&points);
+
+    let (cupx, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+    assert_eq!(cupx.archive_stats().pics_archive_size, pics_size);
+    assert_eq!(cupx.picture_names().collect::<Vec<_>>(), vec!["a.jpg"]);
+}
+
+#[test]
+fn test_picture_reader_progress() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let mut reader = cupx.read_picture("2_1034.jpg").unwrap();
+    assert_eq!(reader.size(), 34858);
+    assert_eq!(reader.bytes_read(), 0);
+
+    let mut buffer = [0u8; 1000];
+    reader.read_exact(&mut buffer).unwrap();
+    assert_eq!(reader.bytes_read(), 1000);
+
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(reader.bytes_read(), reader.size());
+}
+
+#[test]
+fn test_read_picture_cow() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&data[..])).unwrap();
+
+    let mut expected = Vec::new();
+    cupx.read_picture("2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut expected)
+        .unwrap();
+
+    let picture = cupx.read_picture_cow("2_1034.jpg").unwrap();
+    assert!(matches!(picture, Cow::Owned(_)));
+    assert_eq!(picture, expected);
+}
+
+#[test]
+fn test_read_picture_cow_stored() {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("pics/test.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"stored picture data").unwrap();
+        zip.finish().unwrap();
+    }
+    let points = CupxWriter::new(&CupFile::default()).write_to_vec().unwrap();
+    let points_start = points
+        .windows(4)
+        .position(|window| window == b"PK\x05\x06")
+        .unwrap()
+        + 22;
+    buffer.extend_from_slice(&points[points_start..]);
+
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    let picture = cupx.read_picture_cow("test.jpg").unwrap();
+    assert!(matches!(picture, Cow::Borrowed(_)));
+    assert_eq!(&*picture, b"stored picture data");
+}
+
+#[test]
+fn test_read_picture_cow_untrusted_size() {
+    let mut buffer = CupxWriter::new(&CupFile::default())
+        .add_picture("test.jpg", &b"picture data"[..])
+        .write_to_vec()
+        .unwrap();
+
+    // Claim an uncompressed size of almost 4 GiB in the central directory
+    let central_header = buffer
+        .windows(4)
+        .position(|window| window == b"PK\x01\x02")
+        .unwrap();
+    buffer[central_header + 24..central_header + 28].copy_from_slice(&0xFFFF_FFF0u32.to_le_bytes());
+
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&buffer[..])).unwrap();
+    let picture = cupx.read_picture_cow("test.jpg").unwrap();
+    assert_eq!(&*picture, b"picture data");
+}
+
+#[test]
+fn test_uppercase_pics_prefix() {
+    // Pics archive written by an exporter that uses `PICS/` throughout
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("PICS/", options).unwrap();
+        zip.start_file("PICS/Airport.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"airport").unwrap();
+        zip.start_file("PICS/b.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"b").unwrap();
+        zip.finish().unwrap();
+    }
+    let mut airport = waypoint("Airport", 45.0, 7.0);
+    airport.pictures = vec!["Airport.jpg".to_string()];
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(airport);
+    let points = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+    let points_start = points
+        .windows(4)
+        .position(|window| window == b"PK\x05\x06")
+        .unwrap()
+        + 22;
+    buffer.extend_from_slice(&points[points_start..]);
+
+    let (mut cupx, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![]);
+    assert_eq!(
+        cupx.picture_names().collect::<Vec<_>>(),
+        vec!["Airport.jpg", "b.jpg"]
+    );
+    assert_eq!(
+        cupx.picture_paths().collect::<Vec<_>>(),
+        vec!["PICS/Airport.jpg", "PICS/b.jpg"]
+    );
+    assert_eq!(cupx.picture("airport.JPG").unwrap().size, 7);
+    assert_eq!(cupx.unreferenced_pictures(), vec!["b.jpg"]);
+
+    let mut data = Vec::new();
+    cupx.read_picture("Airport.jpg")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"airport");
+    data.clear();
+    cupx.read_picture_by_path("pics/b.jpg")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"b");
+}
+
+#[test]
+fn test_picture_names_skips_directory_entries() {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("pics/", options).unwrap();
+        zip.start_file("pics/a.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"a").unwrap();
+        zip.add_directory("pics/sub/", options).unwrap();
+        zip.start_file("pics/sub/b.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"b").unwrap();
+        zip.finish().unwrap();
+    }
+    let points = CupxWriter::new(&CupFile::default()).write_to_vec().unwrap();
+    let points_start = points
+        .windows(4)
+        .position(|window| window == b"PK\x05\x06")
+        .unwrap()
+        + 22;
+    buffer.extend_from_slice(&points[points_start..]);
+
+    let (cupx, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+    assert_eq!(
+        cupx.picture_names().collect::<Vec<_>>(),
+        vec!["a.jpg", "sub/b.jpg"]
+    );
+    assert_eq!(cupx.pictures_total_size(), 2);
+}
+
+#[test]
+fn test_picture_paths() {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("pics/", options).unwrap();
+        zip.start_file("pics/a.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"a").unwrap();
+        zip.start_file("PICS/b.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"b").unwrap();
+        zip.finish().unwrap();
+    }
+    let points = CupxWriter::new(&CupFile::default()).write_to_vec().unwrap();
+    let points_start = points
+        .windows(4)
+        .position(|window| window == b"PK\x05\x06")
+        .unwrap()
+        + 22;
+    buffer.extend_from_slice(&points[points_start..]);
+
+    let (cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(
+        cupx.picture_paths().collect::<Vec<_>>(),
+        vec!["pics/a.jpg", "PICS/b.jpg"]
+    );
+    assert_eq!(
+        cupx.picture_names().collect::<Vec<_>>(),
+        vec!["a.jpg", "b.jpg"]
+    );
+}
+
+#[test]
+fn test_archive_gap_and_overlap() {
+    let write = |junk: &[u8], comment_length: u16| {
+        let mut buffer = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("pics/foo.jpg", options).unwrap();
+            std::io::Write::write_all(&mut zip, b"foo").unwrap();
+            zip.finish().unwrap();
+        }
+        let len = buffer.len();
+        buffer[len - 2..].copy_from_slice(&comment_length.to_le_bytes());
+        buffer.extend_from_slice(junk);
+        let points = CupxWriter::new(&CupFile::default()).write_to_vec().unwrap();
+        let points_start = points
+            .windows(4)
+            .position(|window| window == b"PK\x05\x06")
+            .unwrap()
+            + 22;
+        buffer.extend_from_slice(&points[points_start..]);
+        buffer
+    };
+    let options = ParseOptions::new().eager_validate(true);
+
+    let buffer = write(b"junk", 0);
+    let result = CupxFile::from_reader_with_options(Cursor::new(&buffer), options.clone());
+    let (_, warnings) = result.unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[ArchiveGap { bytes: 4 }, EmptyWaypointData]");
+
+    // The points archive is read from the start derived from its EOCD record
+    let buffer = write(b"", 4);
+    let result = CupxFile::from_reader(Cursor::new(&buffer)).map(|_| ());
+    assert_compact_debug_snapshot!(result, @r#"Err(Zip(InvalidArchive("No CDFH found")))"#);
+    let result = CupxFile::from_reader_with_options(Cursor::new(&buffer), options.clone());
+    let (cupx, warnings) = result.unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[ArchiveOverlap { bytes: 4 }, EmptyWaypointData]");
+    // The `zip` crate refuses to open the pics archive with the corrupt comment
+    assert_eq!(cupx.picture_names().count(), 0);
+
+    // Without `eager_validate`, the boundary is not checked
+    let buffer = write(b"junk", 0);
+    let (_, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+}
+
+#[test]
+fn test_pictures_without_prefix() {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("foo.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"foo").unwrap();
+        zip.start_file("readme.txt", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"readme").unwrap();
+        zip.start_file("sub/bar.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"bar").unwrap();
+        zip.finish().unwrap();
+    }
+    let points = CupxWriter::new(&CupFile::default()).write_to_vec().unwrap();
+    let points_start = points
+        .windows(4)
+        .position(|window| window == b"PK\x05\x06")
+        .unwrap()
+        + 22;
+    buffer.extend_from_slice(&points[points_start..]);
+
+    let (mut cupx, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[EmptyWaypointData, PicturesWithoutPrefix]");
+    assert_eq!(cupx.picture_names().collect::<Vec<_>>(), vec!["foo.jpg"]);
+    assert_eq!(cupx.picture_paths().collect::<Vec<_>>(), vec!["foo.jpg"]);
+    assert_eq!(cupx.unreferenced_pictures(), vec!["foo.jpg"]);
+    assert_eq!(cupx.picture("FOO.JPG").unwrap().size, 3);
+
+    let mut picture = Vec::new();
+    cupx.read_picture("foo.jpg")
+        .unwrap()
+        .read_to_end(&mut picture)
+        .unwrap();
+    assert_eq!(picture, b"foo");
+    assert!(cupx.read_picture("readme.txt").is_err());
+
+    // Appended pictures are stored at the top level as well
+    let mut appended = Cursor::new(Vec::new());
+    cupx.append_pictures(&[("new.png", b"new")], &mut appended)
+        .unwrap();
+    let (cupx, _) = CupxFile::from_bytes(appended.get_ref()).unwrap();
+    assert_eq!(
+        cupx.picture_names().collect::<Vec<_>>(),
+        vec!["foo.jpg", "new.png"]
+    );
+}
+
+#[test]
+fn test_picture_names_with_extension() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"a"[..])
+        .add_picture("B.JPG", &b"b"[..])
+        .add_picture("c.png", &b"c"[..])
+        .add_picture("jpg", &b"d"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let (cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(
+        cupx.picture_names_with_extension("jpg").collect::<Vec<_>>(),
+        vec!["a.jpg", "B.JPG"]
+    );
+    assert_eq!(
+        cupx.picture_names_matching(|name| name.starts_with('c'))
+            .collect::<Vec<_>>(),
+        vec!["c.png"]
+    );
+}
+
+#[test]
+fn test_picture_info() {
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+
+    let info = cupx.picture("2_1034.JPG").unwrap();
+    assert_eq!(info.size, 34858);
+    assert_eq!(info.compression, zip::CompressionMethod::Deflated);
+    assert!(info.compressed_size < info.size);
+
+    assert_eq!(cupx.picture("missing.jpg"), None);
+}
+
+#[test]
+fn test_picture_info_crc32() {
+    let cup_file = CupFile::default();
+    let data = CupxWriter::new(&cup_file)
+        .add_picture("check.txt", &b"123456789"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let (cupx, _) = CupxFile::from_reader(Cursor::new(&data)).unwrap();
+    let info = cupx.picture("check.txt").unwrap();
+    assert_eq!(info.size, 9);
+    assert_eq!(info.crc32, 0xCBF43926);
+}
+
+#[test]
+fn test_picture_compressions() {
+    let cup_file = CupFile::default();
+    let data = CupxWriter::new(&cup_file)
+        .add_picture("b.txt", &b"bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"[..])
+        .add_picture("a.jpg", &b"jpeg"[..])
+        .picture_compression(zip::CompressionMethod::Stored)
+        .write_to_vec()
+        .unwrap();
+
+    let (cupx, _) = CupxFile::from_reader(Cursor::new(&data)).unwrap();
+    assert_eq!(
+        cupx.picture_compression("A.JPG"),
+        Some(zip::CompressionMethod::Stored)
+    );
+    assert_eq!(cupx.picture_compression("missing.jpg"), None);
+    assert_compact_debug_snapshot!(cupx.picture_compressions().collect::<Vec<_>>(), @r#"[("b.txt", Stored), ("a.jpg", Stored)]"#);
+
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert_eq!(
+        cupx.picture_compressions().count(),
+        cupx.picture_names().count()
+    );
+    assert!(
+        cupx.picture_compressions()
+            .all(|(_, method)| method == zip::CompressionMethod::Deflated)
+    );
+}
+
+#[test]
+fn test_pictures_size() {
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert!(cupx.pictures_compressed_size() > 0);
+    assert!(cupx.pictures_compressed_size() < cupx.pictures_total_size());
+
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    assert_eq!(cupx.pictures_total_size(), 0);
+    assert_eq!(cupx.pictures_compressed_size(), 0);
+}
+
+#[test]
+fn test_compression_ratio() {
+    let cup_file = CupFile::default();
+    let data = CupxWriter::new(&cup_file)
+        .add_picture("text.txt", &[b'a'; 1000][..])
+        .add_picture("empty.txt", &b""[..])
+        .write_to_vec()
+        .unwrap();
+
+    let (cupx, _) = CupxFile::from_bytes(&data).unwrap();
+    let info = cupx.picture("text.txt").unwrap();
+    let ratio = cupx.picture_compression_ratio("TEXT.TXT").unwrap();
+    assert_eq!(ratio, info.compressed_size as f64 / 1000.0);
+    assert!(ratio < 0.1);
+    assert_eq!(cupx.picture_compression_ratio("empty.txt"), None);
+    assert_eq!(cupx.picture_compression_ratio("missing.txt"), None);
+    assert_eq!(
+        cupx.overall_compression_ratio(),
+        cupx.pictures_compressed_size() as f64 / 1000.0
+    );
+
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    assert_eq!(cupx.overall_compression_ratio(), 1.0);
+}
+
+#[test]
+fn test_max_warnings() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("Valid", 45.0, 7.0));
+    for index in 0..5 {
+        cup_file
+            .waypoints
+            .push(waypoint(&format!("Invalid {index}"), 95.0, 7.0));
+    }
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+
+    let (_, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings.len(), 5);
+
+    let options = ParseOptions::new().max_warnings(2);
+    let (cupx, warnings) =
+        CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    assert_eq!(cupx.waypoints().len(), 1);
+    assert_eq!(warnings.len(), 3);
+    assert_compact_debug_snapshot!(warnings[2], @"WarningsTruncated { total: 5 }");
+}
+
+#[test]
+fn test_default_max_warnings() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("Valid", 45.0, 7.0));
+    for index in 0..DEFAULT_MAX_WARNINGS + 5 {
+        cup_file
+            .waypoints
+            .push(waypoint(&format!("Invalid {index}"), 95.0, 7.0));
+    }
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+
+    let (_, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings.len(), DEFAULT_MAX_WARNINGS + 1);
+    assert_compact_debug_snapshot!(warnings.last().unwrap(), @"WarningsTruncated { total: 10005 }");
+
+    let options = ParseOptions::new().max_warnings(usize::MAX);
+    let (_, warnings) = CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    assert_eq!(warnings.len(), DEFAULT_MAX_WARNINGS + 5);
+}
+
+#[test]
+fn test_strict() {
+    let options = ParseOptions::new().strict(true);
+    let file = std::fs::File::open("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    let result = CupxFile::from_reader_with_options(file, options).map(|_| ());
+    assert_compact_debug_snapshot!(result, @"Err(Strict(NoPicturesArchive))");
+
+    let options = ParseOptions::new().strict(true).max_warnings(0);
+    let file = std::fs::File::open("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    let result = CupxFile::from_reader_with_options(file, options).map(|_| ());
+    assert_compact_debug_snapshot!(result, @"Err(Strict(NoPicturesArchive))");
+
+    let options = ParseOptions::new().strict(true);
+    let file = std::fs::File::open("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (_, warnings) = CupxFile::from_reader_with_options(file, options).unwrap();
+    assert_eq!(warnings.len(), 0);
+}
+
+#[test]
+fn test_max_picture_size() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("small.jpg", &[0u8; 10][..])
+        .add_picture("large.jpg", &[0u8; 1000][..])
+        .write_to_vec()
+        .unwrap();
+
+    let options = ParseOptions::new().max_picture_size(100);
+    let (mut cupx, _) = CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    assert!(cupx.read_picture("small.jpg").is_ok());
+
+    let result = cupx.read_picture("large.jpg").map(|_| ());
+    assert_compact_debug_snapshot!(result, @r#"Err(PictureTooLarge { name: "pics/large.jpg", size: 1000, max_size: 100 })"#);
+
+    let result = cupx.all_waypoint_pictures().map(|_| ());
+    assert!(result.is_ok());
+
+    let options = ParseOptions::new()
+        .max_picture_size(100)
+        .eager_validate(true);
+    let result = CupxFile::from_reader_with_options(Cursor::new(&buffer), options).map(|_| ());
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_eager_validate() {
+    let cup_file = CupFile::default();
+    let mut buffer = CupxWriter::new(&cup_file)
+        .add_picture("test.txt", &b"hello hello hello hello"[..])
+        .write_to_vec()
+        .unwrap();
+
+    // Corrupt the first byte of the compressed picture data
+    let name_len = u16::from_le_bytes([buffer[26], buffer[27]]) as usize;
+    let extra_len = u16::from_le_bytes([buffer[28], buffer[29]]) as usize;
+    buffer[30 + name_len + extra_len] ^= 0xFF;
+
+    let result = CupxFile::from_reader(Cursor::new(&buffer));
+    assert!(result.is_ok());
+
+    let options = ParseOptions::new().eager_validate(true);
+    let result = CupxFile::from_reader_with_options(Cursor::new(&buffer), options);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_single_zip_layout() {
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let buffer = CupxWriter::new(cupx.cup_file())
+        .add_picture("test.jpg", &b"data"[..])
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+
+    let (mut result, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[]");
+    assert_eq!(result.waypoints(), cupx.waypoints());
+    assert_eq!(result.picture_names().collect::<Vec<_>>(), vec!["test.jpg"]);
+    assert_eq!(result.picture("test.jpg").unwrap().size, 4);
+
+    let mut data = Vec::new();
+    result
+        .read_picture("test.jpg")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"data");
+
+    let mut pics = Vec::new();
+    let mut points = Vec::new();
+    assert!(!result.split(&mut pics, &mut points).unwrap());
+    assert_eq!(points, buffer);
+
+    // The reader is still usable after `split()` re-opened the archive
+    assert!(result.read_picture("test.jpg").is_ok());
+}
+
+#[test]
+fn test_from_reader_pics_only() {
+    let file = std::fs::File::open("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (mut cupx, warnings) = CupxFile::from_reader_pics_only(file).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert!(cupx.read_picture("2_1034.jpg").is_ok());
+
+    assert_eq!(cupx.waypoints().len(), 126);
+    assert_eq!(cupx.deferred_warnings().len(), 0);
+}
+
+#[test]
+fn test_from_reader_pics_only_deferred_warnings() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("Valid", 45.0, 7.0));
+    cup_file.waypoints.push(waypoint("Invalid", 95.0, 7.0));
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+
+    let (cupx, warnings) = CupxFile::from_reader_pics_only(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(cupx.deferred_warnings().len(), 1);
+    assert_eq!(cupx.waypoints().len(), 1);
+}
+
+#[test]
+fn test_lazy_cup() {
+    let options = ParseOptions::new().lazy_cup(true).max_warnings(0);
+    let file = std::fs::File::open("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    let (cupx, warnings) = CupxFile::from_reader_with_options(file, options).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[WarningsTruncated { total: 1 }]");
+    assert_eq!(cupx.waypoints().len(), 221);
+
+    // The eager warnings additionally contain `NoPicturesArchive`
+    let (_, eager_warnings) =
+        CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    assert_eq!(eager_warnings[0], Warning::NoPicturesArchive);
+    assert_eq!(cupx.deferred_warnings(), &eager_warnings[1..]);
+}
+
+#[test]
+fn test_empty_waypoint_data() {
+    let buffer = CupxWriter::new(&CupFile::default())
+        .add_picture("a.jpg", &b"a"[..])
+        .write_to_vec()
+        .unwrap();
+    let (_, warnings) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[EmptyWaypointData]");
+
+    // Deferred parsing reports the warning on first access
+    let options = ParseOptions::new().lazy_cup(true);
+    let (cupx, warnings) =
+        CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    assert_eq!(warnings, vec![]);
+    assert_compact_debug_snapshot!(cupx.deferred_warnings(), @"[EmptyWaypointData]");
+
+    // A file containing only tasks is not empty
+    let mut cup_file = CupFile::default();
+    cup_file.tasks.push(task(Some("Task"), &["A", "B"]));
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"a"[..])
+        .write_to_vec()
+        .unwrap();
+    let (cupx, warnings) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(warnings, vec![]);
+    assert_eq!(cupx.tasks().len(), 1);
+}
+
+#[test]
+fn test_unreferenced_pictures() {
+    let mut valid = waypoint("Valid", 45.0, 7.0);
+    valid.pictures = vec!["A.jpg".to_string(), "missing.jpg".to_string()];
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(valid);
+
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"a"[..])
+        .add_picture("c.jpg", &b"c"[..])
+        .add_picture("b.jpg", &b"b"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let (cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(cupx.unreferenced_pictures(), vec!["b.jpg", "c.jpg"]);
+}
+
+#[test]
+fn test_picture_coverage() {
+    let mut first = waypoint("First", 45.0, 7.0);
+    first.pictures = vec!["A.jpg".to_string(), "missing.jpg".to_string()];
+    let mut second = waypoint("Second", 45.0, 7.0);
+    second.pictures = vec!["a.jpg".to_string(), "MISSING.JPG".to_string()];
+    second.pictures.push("b.jpg".to_string());
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(first);
+    cup_file.waypoints.push(second);
+
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"a"[..])
+        .add_picture("c.jpg", &b"c"[..])
+        .add_picture("b.jpg", &b"b"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let (cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_compact_debug_snapshot!(cupx.picture_coverage(), @r#"PictureCoverage { stored: 3, referenced: 3, referenced_and_present: 2, referenced_but_missing: ["missing.jpg"], stored_but_unreferenced: ["c.jpg"] }"#);
+
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    assert_eq!(cupx.picture_coverage().stored, 0);
+}
+
+#[test]
+fn test_search_chunk_size() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (expected, _) = CupxFile::from_reader(Cursor::new(&data)).unwrap();
+
+    // Small chunk sizes make it likely that signatures span two chunks
+    for chunk_size in [0, 22, 23, 25, 31, 1000, 10_000_000] {
+        let options = ParseOptions::new().search_chunk_size(chunk_size);
+        let (cupx, warnings) =
+            CupxFile::from_reader_with_options(Cursor::new(&data), options).unwrap();
+        assert_eq!(warnings.len(), 0, "chunk size {chunk_size}");
+        assert_eq!(
+            cupx.picture_names().count(),
+            expected.picture_names().count()
+        );
+        assert_eq!(cupx.waypoints(), expected.waypoints());
+    }
+}
+
+#[test]
+fn test_read_picture_by_path() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+
+    let mut data = Vec::new();
+    cupx.read_picture_by_path("PICS/2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, std::fs::read("tests/fixtures/2_1034.jpg").unwrap());
+
+    let result = cupx.read_picture_by_path("2_1034.jpg").map(|_| ());
+    assert_compact_debug_snapshot!(result, @"Err(Zip(FileNotFound))");
+}
+
+#[test]
+fn test_read_picture_by_path_single_zip() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("test.jpg", &b"data"[..])
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+
+    assert!(cupx.read_picture_by_path("pics/test.jpg").is_ok());
+
+    let result = cupx.read_picture_by_path("POINTS.CUP").map(|_| ());
+    assert_compact_debug_snapshot!(result, @"Err(Zip(FileNotFound))");
+}
+
+#[test]
+fn test_case_sensitive_pictures() {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("pics/Foo.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"upper").unwrap();
+        zip.start_file("pics/foo.jpg", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"lower").unwrap();
+        zip.finish().unwrap();
+    }
+    let points = CupxWriter::new(&CupFile::default()).write_to_vec().unwrap();
+    let points_start = points
+        .windows(4)
+        .position(|window| window == b"PK\x05\x06")
+        .unwrap()
+        + 22;
+    buffer.extend_from_slice(&points[points_start..]);
+
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    cupx.set_case_sensitive_pictures(true);
+
+    let read = |cupx: &mut CupxFile<_>, filename| {
+        let mut data = Vec::new();
+        cupx.read_picture(filename)
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        data
+    };
+    assert_eq!(read(&mut cupx, "Foo.jpg"), b"upper");
+    assert_eq!(read(&mut cupx, "foo.jpg"), b"lower");
+    assert!(cupx.read_picture("FOO.jpg").is_err());
+    assert_eq!(cupx.picture("foo.jpg").unwrap().size, 5);
+    assert!(cupx.picture("FOO.JPG").is_none());
+
+    // The `pics/` prefix is still matched case-insensitively
+    let mut data = Vec::new();
+    cupx.read_picture_by_path("PICS/foo.jpg")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"lower");
+    assert!(cupx.read_picture_by_path("pics/FOO.jpg").is_err());
+
+    cupx.set_case_sensitive_pictures(false);
+    assert!(cupx.picture("FOO.JPG").is_some());
+}
+
+#[test]
+fn test_cup_header() {
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert_eq!(
+        cupx.cup_header(),
+        "name,code,country,lat,lon,elev,style,rwdir,rwlen,rwwidth,freq,desc,userdata,pics"
+    );
+
+    let options = ParseOptions::new().lazy_cup(true);
+    let file = std::fs::File::open("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (lazy, _) = CupxFile::from_reader_with_options(file, options).unwrap();
+    assert_eq!(lazy.cup_header(), cupx.cup_header());
+    assert_eq!(lazy.waypoints(), cupx.waypoints());
+}
+
+#[test]
+fn test_from_archives() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (mut expected, _) = CupxFile::from_bytes(&data).unwrap();
+
+    let mut pics = Vec::new();
+    let mut points = Vec::new();
+    assert!(expected.split(&mut pics, &mut points).unwrap());
+
+    let points_archive = zip::ZipArchive::new(Cursor::new(points.clone())).unwrap();
+    let pics_archive = zip::ZipArchive::new(Cursor::new(pics.clone())).unwrap();
+    let (mut cupx, warnings) = CupxFile::from_archives(points_archive, Some(pics_archive)).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(cupx.waypoints(), expected.waypoints());
+    assert_eq!(
+        cupx.picture_names().collect::<Vec<_>>(),
+        expected.picture_names().collect::<Vec<_>>()
+    );
+    assert_eq!(cupx.picture("2_1034.jpg"), expected.picture("2_1034.jpg"));
+
+    let mut picture = Vec::new();
+    cupx.read_picture("2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut picture)
+        .unwrap();
+    assert_eq!(picture.len(), 34858);
+
+    assert!(cupx.raw_bytes().is_err());
+
+    let points_archive = zip::ZipArchive::new(Cursor::new(points.clone())).unwrap();
+    let (cupx, warnings) =
+        CupxFile::from_archives(points_archive, None::<zip::ZipArchive<Cursor<Vec<u8>>>>).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[NoPicturesArchive]");
+    assert_eq!(cupx.picture_names().count(), 0);
+    assert!(cupx.into_inner().is_none());
+
+    // The archives may use different reader types
+    let points_archive = zip::ZipArchive::new(Cursor::new(&points[..])).unwrap();
+    let pics_archive = zip::ZipArchive::new(Cursor::new(pics)).unwrap();
+    let options = ParseOptions::new().strict(true);
+    let (cupx, _) =
+        CupxFile::from_archives_with_options(points_archive, Some(pics_archive), options).unwrap();
+    assert_eq!(cupx.waypoints(), expected.waypoints());
+    assert_eq!(cupx.picture("2_1034.jpg"), expected.picture("2_1034.jpg"));
+}
+
+#[test]
+fn test_clone() {
+    let data: std::sync::Arc<[u8]> = std::fs::read("tests/fixtures/westalpen_de.cupx")
+        .unwrap()
+        .into();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(data)).unwrap();
+
+    let mut clone = cupx.clone();
+    let thread = std::thread::spawn(move || {
+        let mut picture = Vec::new();
+        clone
+            .read_picture("2_1034.jpg")
+            .unwrap()
+            .read_to_end(&mut picture)
+            .unwrap();
+        (clone.waypoints().len(), picture)
+    });
+
+    let mut picture = Vec::new();
+    cupx.read_picture("2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut picture)
+        .unwrap();
+
+    let (waypoints, cloned_picture) = thread.join().unwrap();
+    assert_eq!(waypoints, cupx.waypoints().len());
+    assert_eq!(cloned_picture, picture);
+}
+
+#[test]
+fn test_picture_format_mismatch() {
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    let jpeg = b"\xFF\xD8\xFF\xE0\0\x10JFIF";
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("renamed.jpg", &png[..])
+        .add_picture("photo.JPEG", &jpeg[..])
+        .add_picture("notes.txt", &png[..])
+        .add_picture("unknown.png", &b"?"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let (_, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+
+    let options = ParseOptions::new().eager_validate(true);
+    let (_, warnings) = CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    assert_compact_debug_snapshot!(warnings, @r#"[EmptyWaypointData, PictureFormatMismatch { name: "renamed.jpg", extension: "jpg", detected: Png }]"#);
+}
+
+#[test]
+fn test_read_picture_typed() {
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("renamed.jpg", &png[..])
+        .add_picture("unknown.png", &b"?"[..])
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+
+    let (format, data) = cupx.read_picture_typed("RENAMED.jpg").unwrap();
+    assert_eq!(format, Some(PictureFormat::Png));
+    assert_eq!(data, png);
+
+    let (format, data) = cupx.read_picture_typed("unknown.png").unwrap();
+    assert_eq!(format, None);
+    assert_eq!(data, b"?");
+
+    assert!(cupx.read_picture_typed("missing.jpg").is_err());
+}
+
+#[test]
+fn test_archive_stats() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let mut pics = Vec::new();
+    let mut points = Vec::new();
+    cupx.split(&mut pics, &mut points).unwrap();
+
+    let stats = cupx.archive_stats();
+    assert_eq!(stats.pics_archive_size, pics.len() as u64);
+    assert_eq!(stats.points_archive_size, points.len() as u64);
+    assert_eq!(stats.pics_entry_count, cupx.picture_names().count());
+    assert_eq!(stats.points_entry_count, 1);
+
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    let stats = cupx.archive_stats();
+    assert_eq!(stats.pics_entry_count, 0);
+    assert_eq!(stats.pics_archive_size, 0);
+
+    let buffer = CupxWriter::new(&CupFile::default())
+        .add_picture("a.jpg", &b"a"[..])
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+    let (cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_compact_debug_snapshot!(cupx.archive_stats(), @"ArchiveStats { pics_entry_count: 2, points_entry_count: 2, pics_archive_size: 284, points_archive_size: 284 }");
+}
+
+#[test]
+fn test_pics_range() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (mut cupx, _) = CupxFile::from_bytes(&data).unwrap();
+    let mut pics = Vec::new();
+    let mut points = Vec::new();
+    cupx.split(&mut pics, &mut points).unwrap();
+    assert_eq!(cupx.pics_range(), Some(0..pics.len() as u64));
+    assert_eq!(cupx.points_start(), pics.len() as u64);
+    assert_eq!(&data[cupx.points_start() as usize..], points);
+
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    assert_eq!(cupx.pics_range(), None);
+    assert_eq!(cupx.points_start(), 0);
+
+    let buffer = CupxWriter::new(&CupFile::default())
+        .add_picture("a.jpg", &b"a"[..])
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+    let (cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(cupx.pics_range(), Some(0..buffer.len() as u64));
+    assert_eq!(cupx.points_start(), 0);
+}
+
+#[test]
+fn test_non_utf8_picture_name() {
+    let mut buffer = CupxWriter::new(&CupFile::default())
+        .add_picture("XXXX.jpg", &b"a"[..])
+        .add_picture("\u{e4}.jpg", &b"b"[..])
+        .write_to_vec()
+        .unwrap();
+
+    // Replace the ASCII name in the local and central headers by Latin-1 bytes,
+    // which leaves the UTF-8 flag unset
+    while let Some(offset) = buffer.windows(4).position(|window| window == b"XXXX") {
+        buffer[offset..offset + 4].copy_from_slice(b"M\xfcnz");
+    }
+
+    let (cupx, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(
+        warnings,
+        vec![
+            Warning::EmptyWaypointData,
+            Warning::NonUtf8PictureName {
+                raw: b"M\xfcnz.jpg".to_vec()
+            }
+        ]
+    );
+    assert_eq!(
+        cupx.picture_names().collect::<Vec<_>>(),
+        vec!["M\u{207f}nz.jpg", "\u{e4}.jpg"]
+    );
+}
+
+#[test]
+fn test_waypoints_by_country() {
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+
+    let by_country = cupx.waypoints_by_country();
+    let counts = by_country
+        .iter()
+        .map(|(country, waypoints)| (country.as_str(), waypoints.len()))
+        .collect::<Vec<_>>();
+    assert_compact_debug_snapshot!(counts, @r#"[("CH", 6), ("FR", 110), ("IT", 9), ("SI", 1)]"#);
+    assert_eq!(
+        cupx.countries().into_iter().collect::<Vec<_>>(),
+        by_country.keys().cloned().collect::<Vec<_>>()
+    );
+    assert_eq!(by_country["FR"][0].name, cupx.waypoints()[0].name);
+
+    let mut cup_file = CupFile::default();
+    let mut without_country = waypoint("None", 45.0, 7.0);
+    without_country.country = String::new();
+    cup_file.waypoints.push(without_country);
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+    let (cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(cupx.waypoints_by_country()[""].len(), 1);
+}
+
+#[test]
+fn test_bounding_box() {
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert_compact_debug_snapshot!(cupx.bounding_box(), @"Some(BoundingBox { min_lat: 43.4757, max_lat: 47.49186666666667, min_lon: 4.690683333333333, max_lon: 13.9107 })");
+
+    let mut cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+    let (cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(cupx.bounding_box(), None);
+
+    cup_file.waypoints.push(waypoint("East", 10.0, 179.5));
+    cup_file.waypoints.push(waypoint("West", -10.0, -179.5));
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+    let (cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(
+        cupx.bounding_box(),
+        Some(BoundingBox {
+            min_lat: -10.0,
+            max_lat: 10.0,
+            min_lon: -179.5,
+            max_lon: 179.5,
+        })
+    );
+}
+
+#[test]
+fn test_task_summaries() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("A", 45.0, 7.0));
+    cup_file.waypoints.push(waypoint("B", 46.0, 7.0));
+    cup_file
+        .tasks
+        .push(task(Some("Triangle"), &["A", "B", "A"]));
+    cup_file.tasks.push(task(None, &["a", "B", "Missing"]));
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+    let (cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+    let summaries = cupx.task_summaries();
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0].name, "Triangle");
+    assert_eq!(summaries[0].point_count, 3);
+    assert_eq!(summaries[0].distance.map(f64::round), Some(222_390.0));
+    assert_eq!(
+        summaries[1],
+        TaskSummary {
+            name: String::new(),
+            point_count: 3,
+            distance: None,
+        }
+    );
+}
+
+#[test]
+fn test_task_distance() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("Start", 45.0, 7.0));
+    cup_file.waypoints.push(waypoint("Finish", 46.0, 7.0));
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+    let (cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+    let distance = cupx.task_distance(&task(None, &["start", "FINISH"]));
+    assert_eq!(distance.map(f64::round), Some(111_195.0));
+    assert_eq!(cupx.task_distance(&task(None, &["Start"])), Some(0.0));
+    assert_eq!(cupx.task_distance(&task(None, &["Start", "Other"])), None);
+}
+
+#[test]
+fn test_task_zones() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("Takeoff", 45.0, 7.0));
+    cup_file.waypoints.push(waypoint("Start", 45.1, 7.0));
+    cup_file.waypoints.push(waypoint("Turn", 45.5, 7.5));
+    cup_file.waypoints.push(waypoint("Finish", 45.1, 7.1));
+    let mut task = task(
+        None,
+        &["Takeoff", "start", "Inline", "Turn", "Finish", "Missing"],
+    );
+    task.points.push((2, waypoint("Inline", 46.0, 8.0)));
+    task.observation_zones = [(2, 20000.0), (0, 3000.0), (1, 500.0), (3, 1000.0), (4, 0.0)]
+        .into_iter()
+        .map(|(index, radius)| ObservationZone {
+            index,
+            style: ObsZoneStyle::Symmetrical,
+            r1: Some(Distance::Meters(radius)),
+            a1: Some(180.0),
+            r2: None,
+            a2: None,
+            a12: None,
+            line: None,
+        })
+        .collect();
+    cup_file.tasks.push(task);
+    let buffer = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+    let (cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+    let zones = cupx
+        .task_zones(&cupx.tasks()[0])
+        .into_iter()
+        .map(|(waypoint, zone)| {
+            (
+                waypoint.name.as_str(),
+                zone.r1.as_ref().map(Distance::to_meters),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_compact_debug_snapshot!(zones, @r#"[("Start", Some(3000.0)), ("Inline", Some(500.0)), ("Turn", Some(20000.0)), ("Finish", Some(1000.0))]"#);
+}
+
+#[test]
+fn test_waypoints_with_style() {
+    let (cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+
+    assert_compact_debug_snapshot!(cupx.count_by_style(), @"[(GrassAirfield, 21), (Outlanding, 104), (SolidAirfield, 1)]");
+    let airfields = cupx
+        .waypoints_with_style(WaypointStyle::GrassAirfield)
+        .collect::<Vec<_>>();
+    assert_eq!(airfields.len(), 21);
+    assert!(
+        airfields
+            .iter()
+            .all(|waypoint| waypoint.style == WaypointStyle::GrassAirfield)
+    );
+
+    let matching = cupx.waypoints_matching(|waypoint| waypoint.name.starts_with('A'));
+    assert!(matching.count() > 0);
+}
+
+#[test]
+fn test_from_reader_recover() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (expected, _) = CupxFile::from_bytes(&data).unwrap();
+
+    // Break the central directory entry of `POINTS.CUP`
+    let mut corrupted = data.clone();
+    let offset = corrupted
+        .windows(4)
+        .rposition(|window| window == b"PK\x01\x02")
+        .unwrap();
+    corrupted[offset..offset + 4].copy_from_slice(b"XXXX");
+
+    assert!(CupxFile::from_bytes(&corrupted).is_err());
+
+    let (mut cupx, warnings) = CupxFile::from_reader_recover(Cursor::new(&corrupted)).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[RecoveredFromCorruptArchive]");
+    assert_eq!(cupx.waypoints(), expected.waypoints());
+    assert_eq!(
+        cupx.picture_names().count(),
+        expected.picture_names().count()
+    );
+    let name = cupx.picture_names().next().unwrap();
+    let mut picture = Vec::new();
+    cupx.read_picture(&name)
+        .unwrap()
+        .read_to_end(&mut picture)
+        .unwrap();
+    assert!(!picture.is_empty());
+
+    // Without an end of central directory record for the points archive
+    let mut corrupted = data.clone();
+    let offset = corrupted
+        .windows(4)
+        .rposition(|window| window == b"PK\x05\x06")
+        .unwrap();
+    corrupted[offset..offset + 4].copy_from_slice(b"XXXX");
+
+    let (cupx, warnings) = CupxFile::from_reader_recover(Cursor::new(&corrupted)).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[RecoveredFromCorruptArchive]");
+    assert_eq!(cupx.waypoints(), expected.waypoints());
+    assert_eq!(
+        cupx.picture_names().count(),
+        expected.picture_names().count()
+    );
+
+    // Intact files are read as usual
+    let (_, warnings) = CupxFile::from_reader_recover(Cursor::new(&data)).unwrap();
+    assert_eq!(warnings, vec![]);
+}
+
+#[test]
+fn test_reject_replacement_chars() {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        zip.start_file("POINTS.CUP", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(
+            &mut zip,
+            b"name,code,country,lat,lon,elev,style\r\n\"M\xfcnchen\",MUC,DE,4808.000N,01134.000E,500.0m,1\r\n",
+        )
+        .unwrap();
+        zip.finish().unwrap();
+    }
+
+    let options = ParseOptions::new()
+        .encoding(Encoding::Utf8)
+        .reject_replacement_chars(true);
+    let result = CupxFile::from_reader_with_options(Cursor::new(&buffer), options.clone());
+    assert_compact_debug_snapshot!(result.map(|_| ()), @"Err(EncodingReplacement { count: 1 })");
+
+    // Without the option, the name is garbled
+    let options = ParseOptions::new().encoding(Encoding::Utf8);
+    let (cupx, _) = CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    assert_eq!(cupx.waypoints()[0].name, "M\u{FFFD}nchen");
+
+    // Auto-detection falls back to Windows-1252, which decodes every byte
+    let options = ParseOptions::new().reject_replacement_chars(true);
+    let (cupx, _) = CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    assert_eq!(cupx.waypoints()[0].name, "München");
+
+    // Deferred parsing reports the error as a warning
+    let options = ParseOptions::new()
+        .encoding(Encoding::Utf8)
+        .reject_replacement_chars(true)
+        .lazy_cup(true);
+    let (cupx, _) = CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    assert_eq!(cupx.waypoints().len(), 0);
+    assert_compact_debug_snapshot!(cupx.deferred_warnings(), @r#"[CupParseIssue { message: "CUP data contains 1 replacement character(s), the encoding is probably wrong", line: None }]"#);
+}
+
+fn waypoint(name: &str, latitude: f64, longitude: f64) -> Waypoint {
+    Waypoint {
+        name: name.to_string(),
+        code: String::new(),
+        country: String::new(),
+        latitude,
+        longitude,
+        elevation: Elevation::Meters(0.0),
+        style: WaypointStyle::Waypoint,
+        runway_direction: None,
+        runway_length: None,
+        runway_width: None,
+        frequency: String::new(),
+        description: String::new(),
+        userdata: String::new(),
+        pictures: vec![],
+    }
+}
+
+fn task(description: Option<&str>, waypoint_names: &[&str]) -> Task {
+    Task {
+        description: description.map(str::to_string),
+        waypoint_names: waypoint_names.iter().map(|name| name.to_string()).collect(),
+        options: None,
+        observation_zones: Vec::new(),
+        points: Vec::new(),
+        multiple_starts: Vec::new(),
+    }
+}
+
+#[test]
+fn test_raw_bytes() {
+    let expected = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    assert_eq!(cupx.raw_bytes().unwrap(), expected);
+    assert!(cupx.read_picture("2_1034.jpg").is_ok());
+
+    let expected = std::fs::read("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    assert_eq!(cupx.raw_bytes().unwrap(), expected);
+}
+
+#[test]
+fn test_into_inner() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+    let (cupx, _) = CupxFile::from_reader(Cursor::new(data.clone())).unwrap();
+    assert_eq!(cupx.into_inner().unwrap().into_inner(), data);
+
+    let data = std::fs::read("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(data.clone())).unwrap();
+    cupx.raw_bytes().unwrap();
+    assert_eq!(cupx.into_inner().unwrap().into_inner(), data);
+}
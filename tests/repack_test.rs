@@ -0,0 +1,140 @@
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, Warning, repack};
+use std::io::{Cursor, Read};
+
+#[test]
+fn test_repack_westalpen() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+
+    let mut buffer = Vec::new();
+    let stats = repack(&mut cupx, Cursor::new(&mut buffer), 9).unwrap();
+    assert_eq!(stats.new_size, buffer.len() as u64);
+    assert_eq!(
+        stats.original_size,
+        std::fs::metadata("tests/fixtures/westalpen_de.cupx")
+            .unwrap()
+            .len()
+    );
+    assert_eq!(stats.pictures_recompressed, cupx.picture_names().count());
+
+    let (mut result, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(result.waypoints(), cupx.waypoints());
+
+    let mut names = result.picture_names().collect::<Vec<_>>();
+    let mut expected_names = cupx.picture_names().collect::<Vec<_>>();
+    names.sort();
+    expected_names.sort();
+    assert_eq!(names, expected_names);
+
+    let mut original = Vec::new();
+    cupx.read_picture("2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut original)
+        .unwrap();
+    let mut repacked = Vec::new();
+    result
+        .read_picture("2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut repacked)
+        .unwrap();
+    assert_eq!(original, repacked);
+}
+
+#[test]
+fn test_repack_no_pictures_archive() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+
+    let mut buffer = Vec::new();
+    let stats = repack(&mut cupx, Cursor::new(&mut buffer), 9).unwrap();
+    assert_eq!(stats.pictures_recompressed, 0);
+
+    let (result, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(result.waypoints().len(), 221);
+}
+
+#[test]
+fn test_repack_invalid_level() {
+    let cup_file = CupFile::default();
+    let data = seeyou_cupx::CupxWriter::new(&cup_file)
+        .add_picture("test.jpg", &b"data"[..])
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&data)).unwrap();
+
+    let result = repack(&mut cupx, Cursor::new(Vec::new()), 1000);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_repack_single_zip() {
+    let cup_file = CupFile::default();
+    let data = seeyou_cupx::CupxWriter::new(&cup_file)
+        .add_picture("test.jpg", &b"data"[..])
+        .layout(seeyou_cupx::CupxLayout::SingleZip)
+        .write_directory_entries(true)
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&data)).unwrap();
+
+    let mut buffer = Vec::new();
+    let stats = repack(&mut cupx, Cursor::new(&mut buffer), 9).unwrap();
+    assert_eq!(stats.pictures_recompressed, 1);
+
+    let (mut result, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+    assert_eq!(result.picture_names().collect::<Vec<_>>(), vec!["test.jpg"]);
+
+    // The `pics/` directory entry belongs to the pics archive only
+    let mut pics = Vec::new();
+    let mut points = Vec::new();
+    assert!(result.split(&mut pics, &mut points).unwrap());
+    let pics = zip::ZipArchive::new(Cursor::new(pics)).unwrap();
+    assert!(pics.file_names().any(|name| name == "pics/"));
+    let points = zip::ZipArchive::new(Cursor::new(points)).unwrap();
+    assert!(!points.file_names().any(|name| name.starts_with("pics/")));
+}
+
+#[test]
+fn test_repack_keeps_points_archive_entries() {
+    let points = b"name,code,country,lat,lon,elev,style,custom\n\"A\",\"A\",DE,4500.000N,00700.000E,0m,1,x\n";
+    let outlandings = CupFile::default();
+    let data = seeyou_cupx::CupxWriter::new(&CupFile::default())
+        .set_raw_points(points)
+        .add_points_file("OUTLANDINGS.CUP", &outlandings)
+        .unwrap()
+        .add_picture("test.jpg", &b"data"[..])
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_reader(Cursor::new(&data)).unwrap();
+
+    let mut buffer = Vec::new();
+    repack(&mut cupx, Cursor::new(&mut buffer), 9).unwrap();
+
+    let (mut result, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    let points_cup = result.read_text_entry("POINTS.CUP").unwrap().unwrap();
+    assert_eq!(points_cup.as_bytes(), points);
+    assert!(result.read_text_entry("OUTLANDINGS.CUP").unwrap().is_some());
+}
+
+#[test]
+fn test_repack_lazy_cup_parse_error() {
+    let points = b"not a CUP file\n\"unterminated";
+    let mut data = Vec::new();
+    seeyou_cupx::CupxWriter::new(&CupFile::default())
+        .set_raw_points(points)
+        .write_unchecked(Cursor::new(&mut data))
+        .unwrap();
+    let options = seeyou_cupx::ParseOptions::new().lazy_cup(true);
+    let (mut cupx, _) = CupxFile::from_reader_with_options(Cursor::new(&data), options).unwrap();
+
+    let mut buffer = Vec::new();
+    repack(&mut cupx, Cursor::new(&mut buffer), 9).unwrap();
+
+    let options = seeyou_cupx::ParseOptions::new().lazy_cup(true);
+    let (mut result, _) =
+        CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+    let points_cup = result.read_text_entry("POINTS.CUP").unwrap().unwrap();
+    assert_eq!(points_cup.as_bytes(), points);
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::waypoint;
+use seeyou_cupx::cup::{CupFile, Encoding};
+use seeyou_cupx::{CupxFile, CupxLayout, CupxWriter, ParseOptions};
+use std::io::{Cursor, Write};
+use zip::CompressionMethod;
+
+const TEXT: &[u8] = b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+#[test]
+fn test_write_preserving_compression() {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        let stored =
+            zip::write::SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = zip::write::SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated);
+        zip.start_file("pics/stored.txt", stored).unwrap();
+        zip.write_all(TEXT).unwrap();
+        zip.start_file("pics/deflated.txt", deflated).unwrap();
+        zip.write_all(TEXT).unwrap();
+        zip.finish().unwrap();
+    }
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("A", &[]));
+    cup_file.waypoints.push(waypoint("B", &[]));
+    let points = CupxWriter::new(&cup_file).write_to_vec().unwrap();
+    let points_start = points
+        .windows(4)
+        .position(|window| window == b"PK\x05\x06")
+        .unwrap()
+        + 22;
+    buffer.extend_from_slice(&points[points_start..]);
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    cupx.waypoints_mut().retain(|waypoint| waypoint.name == "B");
+
+    let mut output = Vec::new();
+    cupx.write_preserving_compression(Cursor::new(&mut output))
+        .unwrap();
+
+    let (result, warnings) = CupxFile::from_bytes(&output).unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(result.waypoints().len(), 1);
+    assert_eq!(result.waypoints()[0].name, "B");
+
+    let stored = result.picture("stored.txt").unwrap();
+    assert_eq!(stored.compression, CompressionMethod::Stored);
+    assert_eq!(stored, cupx.picture("stored.txt").unwrap());
+    let deflated = result.picture("deflated.txt").unwrap();
+    assert_eq!(deflated.compression, CompressionMethod::Deflated);
+    assert_eq!(deflated, cupx.picture("deflated.txt").unwrap());
+}
+
+#[test]
+fn test_write_preserving_compression_single_zip() {
+    let mut cup_file = CupFile::default();
+    cup_file.waypoints.push(waypoint("A", &[]));
+    let extra = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("photo.txt", TEXT)
+        .add_points_file("EXTRA.CUP", &extra)
+        .unwrap()
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    cupx.waypoints_mut().push(waypoint("B", &[]));
+
+    let mut output = Vec::new();
+    cupx.write_preserving_compression(Cursor::new(&mut output))
+        .unwrap();
+
+    let (result, warnings) = CupxFile::from_bytes(&output).unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(result.waypoints().len(), 2);
+    assert_eq!(result.picture("photo.txt"), cupx.picture("photo.txt"));
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(&output)).unwrap();
+    assert!(archive.by_name("EXTRA.CUP").is_ok());
+}
+
+#[test]
+fn test_rewrite_with() {
+    let mut cup_file = CupFile::default();
+    let mut waypoint = waypoint("A", &[]);
+    waypoint.frequency = "123.500".to_string();
+    cup_file.waypoints.push(waypoint);
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("photo.txt", TEXT)
+        .write_to_vec()
+        .unwrap();
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    let mut output = Vec::new();
+    cupx.rewrite_with(
+        |cup_file| {
+            for waypoint in &mut cup_file.waypoints {
+                waypoint.frequency.clear();
+            }
+        },
+        Cursor::new(&mut output),
+    )
+    .unwrap();
+    assert_eq!(cupx.waypoints()[0].frequency, "");
+
+    let (result, warnings) = CupxFile::from_bytes(&output).unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(result.waypoints()[0].frequency, "");
+    assert_eq!(result.picture("photo.txt"), cupx.picture("photo.txt"));
+}
+
+#[test]
+fn test_write_preserving_compression_lazy_parse_error() {
+    let points = b"name,code,country,lat,lon,elev,style\r\n\"M\xfcnchen\",MUC,DE,4808.000N,01134.000E,500.0m,1\r\n";
+    let buffer = CupxWriter::new(&CupFile::default())
+        .set_raw_points(points)
+        .add_picture("a.jpg", &b"data"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let options = ParseOptions::new()
+        .encoding(Encoding::Utf8)
+        .reject_replacement_chars(true)
+        .lazy_cup(true);
+    let (mut cupx, _) = CupxFile::from_reader_with_options(Cursor::new(&buffer), options).unwrap();
+
+    // The original `POINTS.CUP` must not be replaced by an empty one
+    let result = cupx.write_preserving_compression(Cursor::new(Vec::new()));
+    assert!(matches!(
+        result,
+        Err(seeyou_cupx::Error::DeferredCupParse(_))
+    ));
+    let result = cupx.normalize_picture_extensions(Cursor::new(Vec::new()));
+    assert!(matches!(
+        result,
+        Err(seeyou_cupx::Error::DeferredCupParse(_))
+    ));
+}
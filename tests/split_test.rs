@@ -0,0 +1,46 @@
+use seeyou_cupx::CupxFile;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+#[test]
+fn test_split() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+
+    let mut pics = Vec::new();
+    let mut points = Vec::new();
+    let has_pics = cupx.split(&mut pics, &mut points).unwrap();
+    assert!(has_pics);
+
+    let file_size = std::fs::metadata("tests/fixtures/westalpen_de.cupx")
+        .unwrap()
+        .len();
+    assert_eq!((pics.len() + points.len()) as u64, file_size);
+
+    let pics_archive = ZipArchive::new(Cursor::new(&pics)).unwrap();
+    assert_eq!(pics_archive.len(), cupx.picture_names().count());
+
+    let mut points_archive = ZipArchive::new(Cursor::new(&points)).unwrap();
+    assert!(points_archive.by_name("POINTS.CUP").is_ok());
+
+    // Pictures can still be read after splitting
+    let mut buffer = Vec::new();
+    cupx.read_picture("2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut buffer)
+        .unwrap();
+    assert!(!buffer.is_empty());
+}
+
+#[test]
+fn test_split_no_pictures_archive() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/EC25_no_pictures_zip.cupx").unwrap();
+
+    let mut pics = Vec::new();
+    let mut points = Vec::new();
+    let has_pics = cupx.split(&mut pics, &mut points).unwrap();
+    assert!(!has_pics);
+    assert!(pics.is_empty());
+
+    let mut points_archive = ZipArchive::new(Cursor::new(&points)).unwrap();
+    assert!(points_archive.by_name("POINTS.CUP").is_ok());
+}
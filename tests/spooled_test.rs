@@ -0,0 +1,22 @@
+#![cfg(feature = "tempfile")]
+
+use seeyou_cupx::CupxFile;
+use std::io::Read;
+
+#[test]
+fn test_from_read_spooled() {
+    let data = std::fs::read("tests/fixtures/westalpen_de.cupx").unwrap();
+
+    // `Chain` doesn't implement `Seek`, like a network stream
+    let (head, tail) = data.split_at(1000);
+    let (mut cupx, warnings) = CupxFile::from_read_spooled(head.chain(tail)).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(cupx.waypoints().len(), 126);
+    assert!(cupx.read_picture("2_1034.jpg").is_ok());
+    assert_eq!(cupx.raw_bytes().unwrap(), data);
+}
+
+#[test]
+fn test_from_read_spooled_invalid() {
+    assert!(CupxFile::from_read_spooled(&b"not a cupx file"[..]).is_err());
+}
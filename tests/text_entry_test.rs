@@ -0,0 +1,123 @@
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxLayout, CupxWriter};
+use std::io::{Cursor, Write};
+
+fn add_entries(zip: &mut zip::ZipWriter<Cursor<&mut Vec<u8>>>) {
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("DETAILS.TXT", options).unwrap();
+    zip.write_all("Wegpunkte für die Westalpen".as_bytes())
+        .unwrap();
+    zip.start_file("latin1.txt", options).unwrap();
+    zip.write_all(b"f\xFCr").unwrap();
+    zip.add_directory("docs/", options).unwrap();
+}
+
+#[test]
+fn test_read_text_entry() {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("pics/a.jpg", options).unwrap();
+        zip.write_all(b"data a").unwrap();
+        zip.finish().unwrap();
+    }
+    let mut points = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut points));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("POINTS.CUP", options).unwrap();
+        CupFile::default().to_writer(&mut zip).unwrap();
+        add_entries(&mut zip);
+        zip.finish().unwrap();
+    }
+    buffer.extend_from_slice(&points);
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(
+        cupx.read_text_entry("details.txt").unwrap().as_deref(),
+        Some("Wegpunkte für die Westalpen")
+    );
+    assert_eq!(
+        cupx.read_text_entry("latin1.txt").unwrap().as_deref(),
+        Some("f\u{FFFD}r")
+    );
+    assert_eq!(cupx.read_text_entry("README").unwrap(), None);
+    assert_eq!(cupx.read_text_entry("docs/").unwrap(), None);
+    assert_eq!(cupx.read_text_entry("a.jpg").unwrap(), None);
+
+    // The pictures are still readable afterwards
+    assert!(cupx.read_picture("a.jpg").is_ok());
+}
+
+#[test]
+fn test_read_text_entry_single_zip() {
+    let mut buffer = CupxWriter::new(&CupFile::default())
+        .add_picture("a.jpg", &b"data a"[..])
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+    {
+        let mut zip = zip::ZipWriter::new_append(Cursor::new(&mut buffer)).unwrap();
+        add_entries(&mut zip);
+        zip.finish().unwrap();
+    }
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(
+        cupx.read_text_entry("DETAILS.TXT").unwrap().as_deref(),
+        Some("Wegpunkte für die Westalpen")
+    );
+    assert_eq!(cupx.read_text_entry("README").unwrap(), None);
+}
+
+#[test]
+fn test_read_text_entry_strict() {
+    let mut buffer = CupxWriter::new(&CupFile::default())
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+    {
+        let mut zip = zip::ZipWriter::new_append(Cursor::new(&mut buffer)).unwrap();
+        add_entries(&mut zip);
+        zip.finish().unwrap();
+    }
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(
+        cupx.read_text_entry_strict("DETAILS.TXT")
+            .unwrap()
+            .as_deref(),
+        Some("Wegpunkte für die Westalpen")
+    );
+    let result = cupx.read_text_entry_strict("latin1.txt");
+    assert!(matches!(
+        result,
+        Err(seeyou_cupx::Error::EncodingReplacement { count: 1 })
+    ));
+    assert_eq!(cupx.read_text_entry_strict("README").unwrap(), None);
+}
+
+#[test]
+fn test_read_text_entry_too_large() {
+    let mut buffer = CupxWriter::new(&CupFile::default())
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+    {
+        let mut zip = zip::ZipWriter::new_append(Cursor::new(&mut buffer)).unwrap();
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("DETAILS.TXT", options).unwrap();
+        let size = seeyou_cupx::MAX_TEXT_ENTRY_SIZE as usize + 1;
+        zip.write_all(&vec![b'a'; size]).unwrap();
+        zip.finish().unwrap();
+    }
+
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+    let result = cupx.read_text_entry("DETAILS.TXT");
+    assert!(matches!(
+        result,
+        Err(seeyou_cupx::Error::EntryTooLarge { max_size, .. })
+            if max_size == seeyou_cupx::MAX_TEXT_ENTRY_SIZE
+    ));
+}
@@ -1,4 +1,5 @@
-use seeyou_cupx::CupxFile;
+use insta::assert_compact_debug_snapshot;
+use seeyou_cupx::{CupxFile, Warning};
 use std::io::{Cursor, Write};
 use zip::ZipWriter;
 use zip::write::SimpleFileOptions;
@@ -46,8 +47,9 @@ fn test_three_zip_archives() {
     let (cupx, warnings) = CupxFile::from_reader(Cursor::new(&cupx_data)).unwrap();
 
     // Current behavior: successfully parses using the last two ZIPs,
-    // silently ignoring the first ZIP without any warning
-    assert_eq!(warnings.len(), 0);
+    // silently ignoring the first ZIP. The only warning is about the
+    // header-only `POINTS.CUP`.
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
     assert_eq!(cupx.waypoints().len(), 0);
 
     // Successfully reads picture from the second ZIP (pics.zip)
@@ -56,3 +58,37 @@ fn test_three_zip_archives() {
 
     // The first ZIP (extra.zip) is completely ignored without warning
 }
+
+#[test]
+fn test_points_cup_in_pics_archive() {
+    // Create a pics archive that (incorrectly) also contains a POINTS.CUP
+    let mut pics_zip = Vec::new();
+    {
+        let mut zip = ZipWriter::new(Cursor::new(&mut pics_zip));
+        zip.start_file("pics/test.jpg", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"fake image data").unwrap();
+        zip.start_file("points.cup", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"name,code,country,lat,lon,elev,style,rwdir,rwlen,freq,desc\n")
+            .unwrap();
+        zip.finish().unwrap();
+    }
+
+    let mut points_zip = Vec::new();
+    {
+        let mut zip = ZipWriter::new(Cursor::new(&mut points_zip));
+        zip.start_file("POINTS.CUP", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"name,code,country,lat,lon,elev,style,rwdir,rwlen,freq,desc\n")
+            .unwrap();
+        zip.finish().unwrap();
+    }
+
+    let mut cupx_data = Vec::new();
+    cupx_data.extend_from_slice(&pics_zip);
+    cupx_data.extend_from_slice(&points_zip);
+
+    let (_, warnings) = CupxFile::from_reader(Cursor::new(&cupx_data)).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[EmptyWaypointData, PointsCupInPicsArchive]");
+}
@@ -0,0 +1,77 @@
+use seeyou_cupx::cup::CupFile;
+use seeyou_cupx::{CupxFile, CupxWriter, PictureMismatch};
+use std::io::{Cursor, Write};
+
+#[test]
+fn test_verify_pictures_against_dir() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("same.jpg", &b"same"[..])
+        .add_picture("changed.jpg", &b"old"[..])
+        .add_picture("packed_only.jpg", &b"packed"[..])
+        .write_to_vec()
+        .unwrap();
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+    let dir = std::env::temp_dir().join("test_cupx_verify_pictures");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("subfolder")).unwrap();
+    std::fs::write(dir.join("same.jpg"), b"same").unwrap();
+    std::fs::write(dir.join("changed.jpg"), b"new").unwrap();
+    std::fs::write(dir.join("disk_only.jpg"), b"disk").unwrap();
+
+    let mismatches = cupx.verify_pictures_against_dir(&dir).unwrap();
+    assert_eq!(
+        mismatches,
+        vec![
+            PictureMismatch::ContentMismatch("changed.jpg".to_string()),
+            PictureMismatch::MissingOnDisk("packed_only.jpg".to_string()),
+            PictureMismatch::MissingInArchive("disk_only.jpg".to_string()),
+        ]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_verify_pictures_against_missing_dir() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let dir = std::env::temp_dir().join("test_cupx_verify_missing_dir");
+    assert!(cupx.verify_pictures_against_dir(&dir).is_err());
+}
+
+#[test]
+fn test_verify_pictures_outside_dir() {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("pics/../outside.jpg", options).unwrap();
+        zip.write_all(b"outside").unwrap();
+        zip.finish().unwrap();
+    }
+    let mut points = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut points));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("POINTS.CUP", options).unwrap();
+        CupFile::default().to_writer(&mut zip).unwrap();
+        zip.finish().unwrap();
+    }
+    buffer.extend_from_slice(&points);
+    let (mut cupx, _) = CupxFile::from_bytes(&buffer).unwrap();
+
+    let parent = std::env::temp_dir().join("test_cupx_verify_outside");
+    let dir = parent.join("pictures");
+    let _ = std::fs::remove_dir_all(&parent);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(parent.join("outside.jpg"), b"outside").unwrap();
+
+    let mismatches = cupx.verify_pictures_against_dir(&dir).unwrap();
+    assert_eq!(
+        mismatches,
+        vec![PictureMismatch::MissingOnDisk("../outside.jpg".to_string())]
+    );
+
+    std::fs::remove_dir_all(&parent).unwrap();
+}
@@ -1,8 +1,9 @@
 use insta::{assert_binary_snapshot, assert_compact_debug_snapshot};
 use seeyou_cupx::cup::CupFile;
-use seeyou_cupx::{CupxFile, CupxWriter};
+use seeyou_cupx::{CupxFile, CupxLayout, CupxStreamWriter, CupxWriter, PictureOrder, Warning};
 use std::io::{Cursor, Read};
 use std::path::Path;
+use zip::CompressionMethod;
 
 #[test]
 fn test_write_empty() {
@@ -130,6 +131,16 @@ fn test_write_invalid_filename_with_backslash() {
     assert_compact_debug_snapshot!(result, @r#"Err(InvalidFilename("path\\to\\file.jpg"))"#);
 }
 
+#[test]
+fn test_write_invalid_filename_with_pics_prefix() {
+    let cup_file = CupFile::default();
+    let result = CupxWriter::new(&cup_file)
+        .add_picture("PICS/file.jpg", &b"data"[..])
+        .write_to_vec();
+
+    assert_compact_debug_snapshot!(result, @r#"Err(InvalidFilename("PICS/file.jpg"))"#);
+}
+
 #[test]
 fn test_write_nonexistent_path() {
     let cup_file = CupFile::default();
@@ -157,3 +168,593 @@ fn test_write_to_path() {
 
     std::fs::remove_file(&temp_path).unwrap();
 }
+
+#[test]
+fn test_write_with_file_picture() {
+    let cup_file = CupFile::default();
+    let temp_path = std::env::temp_dir().join("test_cupx_writer_file_picture.jpg");
+    let mut file = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp_path)
+        .unwrap();
+    std::io::Write::write_all(&mut file, b"file data").unwrap();
+
+    // The file is read from its beginning, even though it was just written
+    let mut writer = CupxWriter::new(&cup_file);
+    writer.add_picture("test.jpg", &file);
+    for _ in 0..2 {
+        let buffer = writer.write_to_vec().unwrap();
+        let (mut result, _) = CupxFile::from_bytes(&buffer).unwrap();
+        let mut data = Vec::new();
+        result
+            .read_picture("test.jpg")
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, b"file data");
+    }
+
+    drop(file);
+    std::fs::remove_file(&temp_path).unwrap();
+}
+
+#[test]
+fn test_write_to_path_atomic() {
+    let cup_file = CupFile::default();
+    let dir = std::env::temp_dir().join("test_cupx_writer_atomic");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    let path = dir.join("output.cupx");
+    std::fs::write(&path, b"previous content").unwrap();
+
+    CupxWriter::new(&cup_file)
+        .add_picture("test.jpg", &b"test data"[..])
+        .write_to_path_atomic(&path)
+        .unwrap();
+
+    let (result, _) = CupxFile::from_path(&path).unwrap();
+    let names: Vec<_> = result.picture_names().collect();
+    assert_eq!(names, vec!["test.jpg"]);
+
+    // A failed write keeps the previous file and removes the temporary file
+    let result = CupxWriter::new(&cup_file)
+        .add_picture("missing.jpg", Path::new("/nonexistent/missing.jpg"))
+        .write_to_path_atomic(&path);
+    assert!(result.is_err());
+    let (result, _) = CupxFile::from_path(&path).unwrap();
+    assert_eq!(result.picture_names().count(), 1);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    // Concurrent writers to the same path don't collide on the temporary file
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                CupxWriter::new(&cup_file)
+                    .add_picture("test.jpg", &b"test data"[..])
+                    .write_to_path_atomic(&path)
+                    .unwrap();
+            });
+        }
+    });
+    let (result, _) = CupxFile::from_path(&path).unwrap();
+    assert_eq!(result.picture_names().count(), 1);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_write_zip64() {
+    let cup_file = CupFile::default();
+    let mut writer = CupxWriter::new(&cup_file);
+    writer.add_picture("test.jpg", &b"test data"[..]);
+
+    // ZIP64 extended information extra field with 16 bytes of sizes
+    let zip64_extra_field = b"\x01\x00\x10\x00";
+    let has_zip64_extra_field = |buffer: &[u8]| {
+        buffer
+            .windows(zip64_extra_field.len())
+            .any(|window| window == zip64_extra_field)
+    };
+
+    let buffer = writer.write_to_vec().unwrap();
+    assert!(!has_zip64_extra_field(&buffer));
+
+    let buffer = writer.zip64(true).write_to_vec().unwrap();
+    assert!(has_zip64_extra_field(&buffer));
+
+    let (mut result, warnings) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+    let mut data = Vec::new();
+    result
+        .read_picture("test.jpg")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"test data");
+}
+
+#[test]
+fn test_write_auto_compression() {
+    let cup_file = CupFile::default();
+    let jpeg = b"\xFF\xD8\xFF\xE0 fake jpeg data";
+    let bitmap = b"BM fake bitmap data";
+    let text = b"notes";
+
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("photo.jpg", &jpeg[..])
+        .add_picture("map.bmp", &bitmap[..])
+        .add_picture("notes.txt", &text[..])
+        .picture_compression(CompressionMethod::Stored)
+        .auto_compression(true)
+        .write_to_vec()
+        .unwrap();
+
+    let (mut result, _) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_compact_debug_snapshot!(result.picture_compressions().collect::<Vec<_>>(), @r#"[("photo.jpg", Stored), ("map.bmp", Deflated), ("notes.txt", Deflated)]"#);
+
+    let mut data = Vec::new();
+    result
+        .read_picture("map.bmp")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, bitmap);
+}
+
+#[test]
+fn test_validate() {
+    let cup = b"name,code,country,lat,lon,elev,style,pics\n\
+        \"A\",,,4500.000N,00700.000E,0m,1,\"a.jpg;missing.jpg\"\n";
+    let (cup_file, _) = CupFile::from_reader(&cup[..]).unwrap();
+
+    let mut writer = CupxWriter::new(&cup_file);
+    writer
+        .add_picture("a.jpg", &b"a"[..])
+        .add_picture("A.JPG", &b"a"[..])
+        .add_picture("pics/b.jpg", &b"b"[..])
+        .add_picture("c.jpg", Path::new("/nonexistent/c.jpg"));
+    assert_compact_debug_snapshot!(writer.validate(), @r#"[CaseCollision { first: "a.jpg", second: "A.JPG" }, InvalidFilename("pics/b.jpg"), MissingFile { name: "c.jpg", path: "/nonexistent/c.jpg" }, DanglingReference { waypoint: "A", picture: "missing.jpg" }]"#);
+
+    // `write` fails on the first error
+    let result = writer.write_to_vec();
+    assert_compact_debug_snapshot!(result, @r#"Err(InvalidFilename("pics/b.jpg"))"#);
+
+    let writer = CupxWriter::new(&cup_file);
+    assert_compact_debug_snapshot!(writer.validate(), @r#"[DanglingReference { waypoint: "A", picture: "a.jpg" }, DanglingReference { waypoint: "A", picture: "missing.jpg" }]"#);
+}
+
+#[test]
+fn test_stream_writer() {
+    let cup_file = CupFile::default();
+
+    let mut writer = CupxStreamWriter::new(Cursor::new(Vec::new()));
+    writer.write_picture("a.jpg", &b"data a"[..]).unwrap();
+    writer.write_picture("b.jpg", &b"data b"[..]).unwrap();
+    let buffer = writer
+        .finish_pictures_and_write_cup(&cup_file)
+        .unwrap()
+        .into_inner();
+
+    // The picture sizes are unknown in advance, so ZIP64 extensions are written
+    let zip64_extra_field = b"\x01\x00\x10\x00";
+    assert!(
+        buffer
+            .windows(zip64_extra_field.len())
+            .any(|window| window == zip64_extra_field)
+    );
+
+    let (mut result, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+    let names: Vec<_> = result.picture_names().collect();
+    assert_eq!(names, vec!["a.jpg", "b.jpg"]);
+
+    let mut read_data = Vec::new();
+    result
+        .read_picture("b.jpg")
+        .unwrap()
+        .read_to_end(&mut read_data)
+        .unwrap();
+    assert_eq!(read_data, b"data b");
+}
+
+#[test]
+fn test_stream_writer_invalid_filename() {
+    let mut writer = CupxStreamWriter::new(Cursor::new(Vec::new()));
+    let result = writer.write_picture("path/to/file.jpg", &b"data"[..]);
+    assert_compact_debug_snapshot!(result, @r#"Err(InvalidFilename("path/to/file.jpg"))"#);
+}
+
+#[test]
+fn test_write_supplementary_points_file() {
+    let cup_file = CupFile::default();
+    let outlandings = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_points_file("OUTLANDINGS.cup", &outlandings)
+        .unwrap()
+        .write_to_vec()
+        .unwrap();
+
+    let (result, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+    assert_eq!(result.waypoints().len(), 0);
+
+    let points_start = buffer
+        .windows(4)
+        .position(|window| window == b"PK\x05\x06")
+        .unwrap()
+        + 22;
+    let points_archive = zip::ZipArchive::new(Cursor::new(&buffer[points_start..])).unwrap();
+    let names: Vec<_> = points_archive.file_names().collect();
+    assert_eq!(names, vec!["POINTS.CUP", "OUTLANDINGS.cup"]);
+}
+
+#[test]
+fn test_write_invalid_points_filename() {
+    let cup_file = CupFile::default();
+    let mut writer = CupxWriter::new(&cup_file);
+
+    let result = writer.add_points_file("points.cup", &cup_file).map(|_| ());
+    assert_compact_debug_snapshot!(result, @r#"Err(InvalidPointsFilename("points.cup"))"#);
+
+    let result = writer.add_points_file("extra.txt", &cup_file).map(|_| ());
+    assert_compact_debug_snapshot!(result, @r#"Err(InvalidPointsFilename("extra.txt"))"#);
+
+    let result = writer
+        .add_points_file("sub/extra.cup", &cup_file)
+        .map(|_| ());
+    assert_compact_debug_snapshot!(result, @r#"Err(InvalidPointsFilename("sub/extra.cup"))"#);
+}
+
+#[test]
+fn test_write_preview() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("_preview.jpg", &b"replaced"[..])
+        .add_picture("photo.jpg", &b"photo"[..])
+        .set_preview(&b"preview"[..])
+        .write_to_vec()
+        .unwrap();
+
+    let (mut result, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(result.picture_names().count(), 2);
+    assert_eq!(result.preview().unwrap().as_deref(), Some(&b"preview"[..]));
+}
+
+#[test]
+fn test_write_preview_custom_name() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .set_preview(&b"preview"[..])
+        .preview_name("cover.png")
+        .write_to_vec()
+        .unwrap();
+
+    let (mut result, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(result.preview().unwrap(), None);
+    assert_eq!(
+        result.preview_with_name("cover.png").unwrap().as_deref(),
+        Some(&b"preview"[..])
+    );
+}
+
+#[test]
+fn test_write_streaming() {
+    let (mut cupx, _) = CupxFile::from_path("tests/fixtures/westalpen_de.cupx").unwrap();
+    let mut picture = Vec::new();
+    cupx.read_picture("2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut picture)
+        .unwrap();
+
+    // `Vec<u8>` implements `Write`, but not `Seek`
+    let mut buffer = Vec::new();
+    CupxWriter::new(cupx.cup_file())
+        .add_picture("2_1034.jpg", &picture[..])
+        .write_streaming(&mut buffer)
+        .unwrap();
+
+    let (mut result, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(result.waypoints(), cupx.waypoints());
+
+    let mut read_back = Vec::new();
+    result
+        .read_picture("2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut read_back)
+        .unwrap();
+    assert_eq!(read_back, picture);
+}
+
+#[test]
+fn test_write_single_zip() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("test.jpg", &b"data"[..])
+        .layout(CupxLayout::SingleZip)
+        .write_to_vec()
+        .unwrap();
+
+    let eocd_count = buffer
+        .windows(4)
+        .filter(|window| *window == b"PK\x05\x06")
+        .count();
+    assert_eq!(eocd_count, 1);
+
+    let archive = zip::ZipArchive::new(Cursor::new(&buffer)).unwrap();
+    let names: Vec<_> = archive.file_names().collect();
+    assert_eq!(names, vec!["pics/test.jpg", "POINTS.CUP"]);
+}
+
+#[test]
+fn test_write_picture_order() {
+    let cup_file = CupFile::default();
+    let write = |order: PictureOrder| {
+        let buffer = CupxWriter::new(&cup_file)
+            .add_picture("c.jpg", &b"data c"[..])
+            .add_picture("a.jpg", &b"data a"[..])
+            .add_picture("b.jpg", &b"data b"[..])
+            .add_picture("c.jpg", &b"new c"[..])
+            .picture_order(order)
+            .write_to_vec()
+            .unwrap();
+
+        let (result, _) = CupxFile::from_reader(Cursor::new(buffer)).unwrap();
+        result.picture_names().collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        write(PictureOrder::Insertion),
+        vec!["c.jpg", "a.jpg", "b.jpg"]
+    );
+    assert_eq!(
+        write(PictureOrder::Alphabetical),
+        vec!["a.jpg", "b.jpg", "c.jpg"]
+    );
+    assert_eq!(
+        write(PictureOrder::Custom(vec!["b.jpg".to_string()])),
+        vec!["b.jpg", "c.jpg", "a.jpg"]
+    );
+}
+
+#[test]
+fn test_write_unchecked() {
+    let cup_file = CupFile::default();
+    let mut writer = CupxWriter::new(&cup_file);
+    writer.add_picture("a.jpg", &b"data a"[..]);
+
+    let mut checked = Vec::new();
+    writer.write(Cursor::new(&mut checked)).unwrap();
+    let mut unchecked = Vec::new();
+    writer.write_unchecked(Cursor::new(&mut unchecked)).unwrap();
+    assert_eq!(unchecked, checked);
+
+    // Invalid filenames are written as they are
+    writer.add_picture("photos/b.jpg", &b"data b"[..]);
+    assert!(matches!(
+        writer.write(Cursor::new(Vec::new())),
+        Err(seeyou_cupx::Error::InvalidFilename(_))
+    ));
+    let mut unchecked = Vec::new();
+    writer.write_unchecked(Cursor::new(&mut unchecked)).unwrap();
+    let (result, _) = CupxFile::from_bytes(&unchecked).unwrap();
+    assert_compact_debug_snapshot!(result.picture_paths().collect::<Vec<_>>(), @r#"["pics/a.jpg", "pics/photos/b.jpg"]"#);
+}
+
+#[test]
+fn test_write_without_pics_prefix() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"data a"[..])
+        .add_picture("photos/b.jpg", &b"data b"[..])
+        .write_directory_entries(true)
+        .prefix_pictures(false)
+        .write_to_vec()
+        .unwrap();
+
+    let (result, warnings) = CupxFile::from_bytes(&buffer).unwrap();
+    assert_compact_debug_snapshot!(warnings, @"[EmptyWaypointData, PicturesWithoutPrefix]");
+    assert_compact_debug_snapshot!(result.picture_paths().collect::<Vec<_>>(), @r#"["a.jpg"]"#);
+
+    let pics_end = result.pics_range().unwrap().end as usize;
+    let archive = zip::ZipArchive::new(std::io::Cursor::new(&buffer[..pics_end])).unwrap();
+    assert_compact_debug_snapshot!(archive.file_names().collect::<Vec<_>>(), @r#"["a.jpg", "photos/b.jpg"]"#);
+
+    for path in [
+        "",
+        "/a.jpg",
+        "photos//a.jpg",
+        "../a.jpg",
+        "a\\b.jpg",
+        "points.cup",
+    ] {
+        let result = CupxWriter::new(&cup_file)
+            .add_picture(path, &b"data"[..])
+            .prefix_pictures(false)
+            .write_to_vec();
+        assert!(
+            matches!(result, Err(seeyou_cupx::Error::InvalidFilename(_))),
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn test_write_directory_entries() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"data a"[..])
+        .write_directory_entries(true)
+        .write_to_vec()
+        .unwrap();
+
+    let end = buffer.windows(4).rposition(|w| w == b"PK\x05\x06").unwrap();
+    let boundary = buffer[..end]
+        .windows(4)
+        .rposition(|w| w == b"PK\x05\x06")
+        .unwrap()
+        + 22;
+    let pics = zip::ZipArchive::new(Cursor::new(&buffer[..boundary])).unwrap();
+    assert_eq!(
+        pics.file_names().collect::<Vec<_>>(),
+        vec!["pics/", "pics/a.jpg"]
+    );
+
+    let (result, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+    assert_eq!(result.picture_names().collect::<Vec<_>>(), vec!["a.jpg"]);
+}
+
+#[test]
+fn test_write_uppercase_pics_prefix() {
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"data a"[..])
+        .write_directory_entries(true)
+        .pics_prefix_case(true)
+        .write_to_vec()
+        .unwrap();
+
+    let (mut result, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(
+        result.picture_paths().collect::<Vec<_>>(),
+        vec!["PICS/a.jpg"]
+    );
+    assert_eq!(result.picture_names().collect::<Vec<_>>(), vec!["a.jpg"]);
+
+    let mut data = Vec::new();
+    result
+        .read_picture("a.jpg")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"data a");
+}
+
+#[test]
+fn test_write_compression() {
+    let cup_file = CupFile::default();
+    let compression = |pictures, points| {
+        let buffer = CupxWriter::new(&cup_file)
+            .add_picture("a.jpg", &b"data a"[..])
+            .picture_compression(pictures)
+            .points_compression(points)
+            .layout(CupxLayout::SingleZip)
+            .write_to_vec()
+            .unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(&buffer)).unwrap();
+        let pictures = archive.by_name("pics/a.jpg").unwrap().compression();
+        let points = archive.by_name("POINTS.CUP").unwrap().compression();
+        (pictures, points)
+    };
+
+    for pictures in [CompressionMethod::Stored, CompressionMethod::Deflated] {
+        for points in [CompressionMethod::Stored, CompressionMethod::Deflated] {
+            assert_eq!(compression(pictures, points), (pictures, points));
+        }
+    }
+
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"data a"[..])
+        .picture_compression(CompressionMethod::Stored)
+        .write_to_vec()
+        .unwrap();
+    let (result, warnings) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(warnings, vec![Warning::EmptyWaypointData]);
+    let info = result.picture("a.jpg").unwrap();
+    assert_eq!(info.compression, CompressionMethod::Stored);
+}
+
+#[test]
+fn test_write_raw_points() {
+    let points = b"name,code,country,lat,lon,elev,style,rwdir,rwlen,rwwidth,freq,desc,vendor\r\n\
+\"Home\",\"HOME\",DE,5100.000N,00700.000E,100.0m,2,,,,,,extra\r\n";
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture("a.jpg", &b"data a"[..])
+        .set_raw_points(points)
+        .write_to_vec()
+        .unwrap();
+
+    let (result, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(result.waypoints().len(), 1);
+    assert_eq!(result.waypoints()[0].name, "Home");
+    assert_eq!(result.picture_names().collect::<Vec<_>>(), vec!["a.jpg"]);
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(&buffer)).unwrap();
+    let mut written = Vec::new();
+    archive
+        .by_name("POINTS.CUP")
+        .unwrap()
+        .read_to_end(&mut written)
+        .unwrap();
+    assert_eq!(written, points);
+
+    let result = CupxWriter::new(&cup_file)
+        .set_raw_points(&[])
+        .write_to_vec();
+    assert!(matches!(result, Err(seeyou_cupx::Error::EmptyRawPoints)));
+}
+
+#[test]
+fn test_write_max_output_size() {
+    let cup_file = CupFile::default();
+    let picture = vec![0x42; 1000];
+    let mut writer = CupxWriter::new(&cup_file);
+    writer.add_picture("a.jpg", &picture[..]);
+
+    let size = writer.write_to_vec().unwrap().len() as u64;
+    assert!(writer.max_output_size(size).write_to_vec().is_ok());
+
+    let result = writer.max_output_size(size - 1).write_to_vec();
+    assert!(matches!(
+        result,
+        Err(seeyou_cupx::Error::OutputTooLarge { max_size }) if max_size == size - 1
+    ));
+
+    let result = writer.max_output_size(100).write_streaming(Vec::new());
+    assert!(matches!(
+        result,
+        Err(seeyou_cupx::Error::OutputTooLarge { max_size: 100 })
+    ));
+}
+
+#[test]
+fn test_write_picture_from_cupx() {
+    let source = Path::new("tests/fixtures/westalpen_de.cupx");
+    let cup_file = CupFile::default();
+    let buffer = CupxWriter::new(&cup_file)
+        .add_picture_from_cupx("a.jpg", source, "2_1034.jpg")
+        .add_picture_from_cupx("b.jpg", source, "2_1034.JPG")
+        .write_to_vec()
+        .unwrap();
+
+    let (mut original, _) = CupxFile::from_path(source).unwrap();
+    let mut expected = Vec::new();
+    original
+        .read_picture("2_1034.jpg")
+        .unwrap()
+        .read_to_end(&mut expected)
+        .unwrap();
+
+    let (mut result, _) = CupxFile::from_reader(Cursor::new(&buffer)).unwrap();
+    for name in ["a.jpg", "b.jpg"] {
+        let mut data = Vec::new();
+        result
+            .read_picture(name)
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, expected);
+    }
+
+    let result = CupxWriter::new(&cup_file)
+        .add_picture_from_cupx("a.jpg", source, "missing.jpg")
+        .write_to_vec();
+    assert!(result.is_err());
+}